@@ -18,6 +18,8 @@ pub enum Contracts {
         query = QueryMsg
         // cw20_send = ...             
         // migrate = ...                
+        // depends_on = [OtherContract] // contracts that must be instantiated first
+        // salt = "my_salt" // instantiates at a predictable address with MsgInstantiateContract2
         // rename = "cw20"               // | You should only need to change these
         // bin_name = "cw20"             // | three ff you have a non-standard workspace
         // path = "contracts/cw20_base"  // | layout.
@@ -31,29 +33,84 @@ pub enum Contracts {
 // There are a few default methods that you can override.
 // These apply for have preprogrammed messages for the various stages of deployment.
 // Generally you'll want to match on the Contracts enum and handle the logic for each contract.
-// You'll also likely want to use lazy_static to create the messages you need.
+// The msg builders get a DeployContext with the addresses and code ids of the contracts deployed so far.
 impl Deploy for Contracts {
     // // This method gets the preprogrammed instantiate msg for the contract.
-    // fn instantiate_msg(&self) -> Option<Box<dyn Msg>> {
+    // fn instantiate_msg(&self, ctx: &DeployContext) -> Option<Box<dyn Msg>> {
     //     match self {
-    //         Contracts::MyContract { .. } => Some(Box::new(INSTANTIATE.to_owned())),
+    //         Contracts::MyContract { .. } => Some(Box::new(instantiate_msg(ctx))),
     //     }
     // }
 
+    // // These are the funds sent along with the instantiate msg.
+    // fn instantiate_funds(&self) -> Vec<Coin> {
+    //     match self {
+    //         Contracts::MyContract { .. } => vec![Coin { denom: "uosmo".parse().unwrap(), amount: 1_000_000 }],
+    //     }
+    // }
+
+    // // This is who may instantiate the code once it has been stored.
+    // fn instantiate_permission(&self) -> Option<InstantiatePermission> {
+    //     Some(InstantiatePermission::Deployer)
+    // }
+
     // // This method gets the preprogrammed migrate msg for the contract.
-    // fn migrate_msg(&self) -> Option<Box<dyn Msg>> {
+    // fn migrate_msg(&self, ctx: &DeployContext) -> Option<Box<dyn Msg>> {
     //     match self {
-    //         Contracts::MyContract { .. } => Some(Box::new(INSTANTIATE.to_owned())),
+    //         Contracts::MyContract { .. } => Some(Box::new(MigrateMsg {})),
+    //     }
+    // }
+
+    // // This method gets the preprogrammed migrate msg used to roll the contract back to `code_id`.
+    // fn rollback_msg(&self, ctx: &DeployContext, code_id: u64) -> Option<Box<dyn Msg>> {
+    //     match self {
+    //         Contracts::MyContract { .. } => Some(Box::new(MigrateMsg {})),
     //     }
     // }
 
     // // This method gets the preprogrammed set up msgs for the contract.
-    // fn set_up_msgs(&self) -> Vec<Box<dyn Msg>> {
+    // fn set_up_msgs(&self, ctx: &DeployContext) -> Vec<Box<dyn Msg>> {
     //     match self {
-    //         Contracts::MyContract => CW20_MINT
-    //             .iter()
-    //             .map(|x| Box::new(x.clone()) as Box<dyn Msg>)
+    //         Contracts::MyContract => cw20_mint(ctx)
+    //             .into_iter()
+    //             .map(|x| Box::new(x) as Box<dyn Msg>)
     //             .collect(),
     //     }
     // }
+
+    // // Set up msgs that are executed on another contract or address, such as registering with a factory.
+    // fn set_up_items(&self, ctx: &DeployContext) -> Vec<SetUpItem<Box<dyn Msg>>> {
+    //     match self {
+    //         Contracts::MyContract => vec![SetUpItem {
+    //             target: SetUpTarget::Addr("&factory".into()),
+    //             msg: Box::new(FactoryExecuteMsg::Register { vault: ctx.addr("my_contract").unwrap() }),
+    //             funds: vec![],
+    //             role: None, // or Some("operator".into()) to sign with the key bound to that role
+    //         }],
+    //     }
+    // }
+
+    // // These queries are checked after set up, and by the verify command.
+    // fn verify_queries(&self, ctx: &DeployContext) -> Vec<VerifyQuery<Box<dyn Msg>>> {
+    //     match self {
+    //         Contracts::MyContract => vec![VerifyQuery {
+    //             msg: Box::new(QueryMsg::Minter {}),
+    //             expected: Expected::Subset(json!({ "minter": "&my_contract" })),
+    //         }],
+    //     }
+    // }
+
+    // // Runs after every deployment stage, for example to write addresses into a frontend file.
+    // // Overriding the hooks requires `#[async_trait(?Send)]` on this impl.
+    // async fn after_stage(
+    //     &self,
+    //     ctx: &DeployContext,
+    //     stage: &DeploymentStage,
+    //     result: &anyhow::Result<()>,
+    // ) -> anyhow::Result<()> {
+    //     if let (DeploymentStage::Instantiate { .. }, Ok(())) = (stage, result) {
+    //         std::fs::write("frontend/addresses.json", serde_json::to_vec(&ctx.env.contracts)?)?;
+    //     }
+    //     Ok(())
+    // }
 }
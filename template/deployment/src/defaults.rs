@@ -1,7 +1,3 @@
 // Use this file to define the various default message you want deploy to use
-use lazy_static::lazy_static;
-
-// Using lazy_static helps us create the messages that we need for the various deployment stages.
-lazy_static! {
-// Enter all your instantiate and set up messages here.
-}
+// Enter all your instantiate and set up messages here, as functions that build them.
+// Take a `&DeployContext` when a message needs the address or code id of another contract.
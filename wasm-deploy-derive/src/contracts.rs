@@ -3,8 +3,10 @@ use std::collections::BTreeMap;
 use convert_case::{Case, Casing};
 
 use syn::{
+    bracketed,
     parse::{Parse, ParseStream},
     parse_quote,
+    punctuated::Punctuated,
     token::Brace,
     Expr, ExprMatch, Ident, ItemEnum, ItemImpl, Path, Token,
 };
@@ -44,6 +46,8 @@ pub fn get_contracts(item_enum: ItemEnum) -> Vec<Contract> {
                 query: options.query,
                 migrate: options.migrate,
                 cw20_send: options.cw20_send,
+                depends_on: options.depends_on,
+                salt: options.salt,
             }
         })
         .collect()
@@ -152,6 +156,18 @@ pub fn generate_impl(enum_ident: &Ident, contracts: &[Contract]) -> ItemImpl {
         }
     });
 
+    let depends_on_match = generate_match(enum_ident, contracts, |contract| {
+        let depends_on = &contract.depends_on;
+        parse_quote!(vec![#(#enum_ident::#depends_on),*])
+    });
+
+    let salt_match = generate_match(enum_ident, contracts, |contract| match &contract.salt {
+        Some(salt) => {
+            parse_quote!(Some(::std::convert::AsRef::<[u8]>::as_ref(&#salt).to_vec()))
+        }
+        None => parse_quote!(None),
+    });
+
     parse_quote! {
         impl ::wasm_deploy::contract::ContractInteractive for #enum_ident {
             fn name(&self) -> String {
@@ -166,6 +182,12 @@ pub fn generate_impl(enum_ident: &Ident, contracts: &[Contract]) -> ItemImpl {
             fn admin(&self) -> String {
                 #admin_match
             }
+            fn depends_on(&self) -> Vec<Self> {
+                #depends_on_match
+            }
+            fn salt(&self) -> Option<Vec<u8>> {
+                #salt_match
+            }
             fn instantiate(&self) -> Result<Box<dyn ::wasm_deploy::contract::Msg>, ::anyhow::Error> {
                 #instantiate_match
             }
@@ -188,6 +210,7 @@ pub fn generate_impl(enum_ident: &Ident, contracts: &[Contract]) -> ItemImpl {
 enum Value {
     Path(syn::Path),
     Expr(syn::Expr),
+    Idents(Vec<syn::Ident>),
 }
 
 impl Value {
@@ -206,6 +229,14 @@ impl Value {
             panic!("expected an expression");
         }
     }
+
+    fn unwrap_idents(self) -> Vec<syn::Ident> {
+        if let Self::Idents(i) = self {
+            i
+        } else {
+            panic!("expected a list of contracts");
+        }
+    }
 }
 
 struct Pair((Ident, Value));
@@ -216,13 +247,19 @@ impl Parse for Pair {
         input.parse::<Token![=]>()?;
         let v = match k.to_string().as_str() {
             // "rename" => Value::Str(input.parse::<LitStr>()?),
-            "admin" | "rename" | "bin_name" | "path" => Value::Expr(input.parse::<Expr>()?),
+            "admin" | "rename" | "bin_name" | "path" | "salt" => Value::Expr(input.parse::<Expr>()?),
             "instantiate" | "execute" | "query" | "migrate" | "cw20_send" => {
                 Value::Path(input.parse::<Path>()?)
             }
+            "depends_on" => {
+                let content;
+                bracketed!(content in input);
+                let idents = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?;
+                Value::Idents(idents.into_iter().collect())
+            }
             _ => return Err(syn::Error::new(
                 k.span(),
-                "expected one of: rename, admin, instantiate, execute, query, migrate, cw20_send",
+                "expected one of: rename, admin, instantiate, execute, query, migrate, cw20_send, depends_on, salt",
             )),
         };
 
@@ -241,6 +278,8 @@ pub struct Contract {
     query: Option<Path>,
     migrate: Option<Path>,
     cw20_send: Option<Path>,
+    depends_on: Vec<Ident>,
+    salt: Option<Expr>,
 }
 
 pub struct Options {
@@ -253,6 +292,8 @@ pub struct Options {
     query: Option<Path>,
     migrate: Option<Path>,
     cw20_send: Option<Path>,
+    depends_on: Vec<Ident>,
+    salt: Option<Expr>,
 }
 
 impl Parse for Options {
@@ -288,6 +329,13 @@ impl Parse for Options {
             .remove(&parse_quote!(cw20_send))
             .map(|ty| ty.unwrap_type());
 
+        let depends_on = map
+            .remove(&parse_quote!(depends_on))
+            .map(|x| x.unwrap_idents())
+            .unwrap_or_default();
+
+        let salt = map.remove(&parse_quote!(salt)).map(|x| x.unwrap_expr());
+
         if let Some((invalid_option, _)) = map.into_iter().next() {
             panic!("unknown generate_api option: {}", invalid_option);
         }
@@ -302,6 +350,8 @@ impl Parse for Options {
             query,
             migrate,
             cw20_send,
+            depends_on,
+            salt,
         })
     }
 }
@@ -0,0 +1,156 @@
+use cosm_utils::{
+    chain::msg::Msg,
+    config::cfg::ChainConfig,
+    modules::{auth::model::Address, cosmwasm::error::CosmwasmError},
+    signing_key::key::SigningKey,
+};
+use cosmrs::proto::cosmwasm::wasm::v1::{MsgClearAdmin, MsgUpdateAdmin};
+use serde::{Deserialize, Serialize};
+use tendermint_rpc::{endpoint::broadcast::tx_commit, HttpClient};
+
+use crate::{
+    query::query_contract_info,
+    tx::{broadcast, TxConfig},
+};
+
+/// Queries the current admin of a contract.
+/// Returns `None` if the contract has no admin.
+pub async fn query_admin(client: &HttpClient, address: &str) -> anyhow::Result<Option<String>> {
+    Ok(query_contract_info(client, address)
+        .await?
+        .map(|x| x.admin)
+        .filter(|admin| !admin.is_empty()))
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct UpdateAdminRequest {
+    pub contract_addr: Address,
+    pub new_admin: Address,
+}
+
+impl UpdateAdminRequest {
+    pub fn to_proto(self, signer_addr: Address) -> UpdateAdminProto {
+        UpdateAdminProto {
+            signer_addr,
+            contract_addr: self.contract_addr,
+            new_admin: self.new_admin,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct UpdateAdminProto {
+    pub signer_addr: Address,
+    pub contract_addr: Address,
+    pub new_admin: Address,
+}
+
+impl Msg for UpdateAdminProto {
+    type Proto = MsgUpdateAdmin;
+    type Err = CosmwasmError;
+}
+
+impl TryFrom<MsgUpdateAdmin> for UpdateAdminProto {
+    type Error = CosmwasmError;
+
+    fn try_from(msg: MsgUpdateAdmin) -> Result<Self, Self::Error> {
+        Ok(Self {
+            signer_addr: msg.sender.parse()?,
+            contract_addr: msg.contract.parse()?,
+            new_admin: msg.new_admin.parse()?,
+        })
+    }
+}
+
+impl TryFrom<UpdateAdminProto> for MsgUpdateAdmin {
+    type Error = CosmwasmError;
+
+    fn try_from(req: UpdateAdminProto) -> Result<Self, Self::Error> {
+        Ok(Self {
+            sender: req.signer_addr.into(),
+            new_admin: req.new_admin.into(),
+            contract: req.contract_addr.into(),
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct ClearAdminRequest {
+    pub contract_addr: Address,
+}
+
+impl ClearAdminRequest {
+    pub fn to_proto(self, signer_addr: Address) -> ClearAdminProto {
+        ClearAdminProto {
+            signer_addr,
+            contract_addr: self.contract_addr,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct ClearAdminProto {
+    pub signer_addr: Address,
+    pub contract_addr: Address,
+}
+
+impl Msg for ClearAdminProto {
+    type Proto = MsgClearAdmin;
+    type Err = CosmwasmError;
+}
+
+impl TryFrom<MsgClearAdmin> for ClearAdminProto {
+    type Error = CosmwasmError;
+
+    fn try_from(msg: MsgClearAdmin) -> Result<Self, Self::Error> {
+        Ok(Self {
+            signer_addr: msg.sender.parse()?,
+            contract_addr: msg.contract.parse()?,
+        })
+    }
+}
+
+impl TryFrom<ClearAdminProto> for MsgClearAdmin {
+    type Error = CosmwasmError;
+
+    fn try_from(req: ClearAdminProto) -> Result<Self, Self::Error> {
+        Ok(Self {
+            sender: req.signer_addr.into(),
+            contract: req.contract_addr.into(),
+        })
+    }
+}
+
+pub async fn update_admin_batch_commit(
+    client: &HttpClient,
+    chain_cfg: &ChainConfig,
+    reqs: Vec<UpdateAdminRequest>,
+    key: &SigningKey,
+    tx_config: &TxConfig,
+) -> anyhow::Result<Option<tx_commit::Response>> {
+    let sender_addr = tx_config.sender_addr(key, chain_cfg).await?;
+
+    let msgs = reqs
+        .into_iter()
+        .map(|r| r.to_proto(sender_addr.clone()))
+        .collect::<Vec<_>>();
+
+    broadcast(client, chain_cfg, msgs, key, tx_config).await
+}
+
+pub async fn clear_admin_batch_commit(
+    client: &HttpClient,
+    chain_cfg: &ChainConfig,
+    reqs: Vec<ClearAdminRequest>,
+    key: &SigningKey,
+    tx_config: &TxConfig,
+) -> anyhow::Result<Option<tx_commit::Response>> {
+    let sender_addr = tx_config.sender_addr(key, chain_cfg).await?;
+
+    let msgs = reqs
+        .into_iter()
+        .map(|r| r.to_proto(sender_addr.clone()))
+        .collect::<Vec<_>>();
+
+    broadcast(client, chain_cfg, msgs, key, tx_config).await
+}
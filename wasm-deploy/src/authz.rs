@@ -0,0 +1,107 @@
+use cosm_utils::{
+    config::cfg::ChainConfig, modules::auth::model::Address, signing_key::key::SigningKey,
+};
+use cosmrs::{
+    proto::{
+        cosmos::authz::v1beta1::{GenericAuthorization, Grant, MsgExec, MsgGrant},
+        cosmwasm::wasm::v1::{
+            MsgClearAdmin, MsgExecuteContract, MsgInstantiateContract, MsgMigrateContract,
+            MsgStoreCode, MsgUpdateAdmin,
+        },
+        traits::TypeUrl,
+        Any,
+    },
+    tendermint::Time,
+};
+use prost::Message;
+use prost_types::Timestamp;
+use tendermint_rpc::{endpoint::broadcast::tx_commit, HttpClient};
+
+use crate::{
+    instantiate2::MsgInstantiateContract2,
+    permissions::MsgUpdateInstantiateConfig,
+    proposal::MSG_SUBMIT_PROPOSAL,
+    tx::{broadcast, TxConfig},
+    tx_file::EncodedMsg,
+};
+
+pub const MSG_EXEC: &str = "/cosmos.authz.v1beta1.MsgExec";
+pub const MSG_GRANT: &str = "/cosmos.authz.v1beta1.MsgGrant";
+pub const GENERIC_AUTHORIZATION: &str = "/cosmos.authz.v1beta1.GenericAuthorization";
+
+/// The msg types a grantee needs to deploy and administer contracts,
+/// which are those produced by `execute_deployment` and the admin commands.
+pub const GRANTED_MSG_TYPES: [&str; 9] = [
+    MsgStoreCode::TYPE_URL,
+    MsgInstantiateContract::TYPE_URL,
+    MsgInstantiateContract2::TYPE_URL,
+    MsgExecuteContract::TYPE_URL,
+    MsgMigrateContract::TYPE_URL,
+    MsgUpdateAdmin::TYPE_URL,
+    MsgClearAdmin::TYPE_URL,
+    MsgUpdateInstantiateConfig::TYPE_URL,
+    MSG_SUBMIT_PROPOSAL,
+];
+
+/// Wraps a msg sent from the granter in a MsgExec sent by the grantee.
+pub fn exec_msg(grantee: &Address, msg: Any) -> Any {
+    Any {
+        type_url: MSG_EXEC.to_string(),
+        value: MsgExec {
+            grantee: grantee.to_string(),
+            msgs: vec![msg],
+        }
+        .encode_to_vec(),
+    }
+}
+
+/// A MsgGrant of a generic authorization for every msg type in `GRANTED_MSG_TYPES`.
+pub fn grant_msgs(granter: &Address, grantee: &Address, expiration: Option<Time>) -> Vec<Any> {
+    GRANTED_MSG_TYPES
+        .iter()
+        .map(|msg_type| Any {
+            type_url: MSG_GRANT.to_string(),
+            value: MsgGrant {
+                granter: granter.to_string(),
+                grantee: grantee.to_string(),
+                grant: Some(Grant {
+                    authorization: Some(Any {
+                        type_url: GENERIC_AUTHORIZATION.to_string(),
+                        value: GenericAuthorization {
+                            msg: msg_type.to_string(),
+                        }
+                        .encode_to_vec(),
+                    }),
+                    expiration: expiration.map(|x| Timestamp {
+                        seconds: x.unix_timestamp(),
+                        nanos: 0,
+                    }),
+                }),
+            }
+            .encode_to_vec(),
+        })
+        .collect()
+}
+
+/// Grants `grantee` every msg type in `GRANTED_MSG_TYPES` on behalf of `granter`.
+/// The tx is signed by `key`, which must be the granter unless the tx is only generated.
+pub async fn grant_commit(
+    client: &HttpClient,
+    chain_cfg: &ChainConfig,
+    granter: &Address,
+    grantee: &Address,
+    expiration: Option<Time>,
+    key: &SigningKey,
+    tx_config: &TxConfig,
+) -> anyhow::Result<Option<tx_commit::Response>> {
+    // The grant itself is signed by the granter, so it must not go through authz
+    let tx_config = TxConfig {
+        authz_granter: None,
+        ..tx_config.clone()
+    };
+    let msgs = grant_msgs(granter, grantee, expiration)
+        .into_iter()
+        .map(EncodedMsg)
+        .collect();
+    broadcast(client, chain_cfg, msgs, key, &tx_config).await
+}
@@ -0,0 +1,200 @@
+use std::{
+    collections::BTreeMap,
+    fs::{create_dir_all, OpenOptions},
+    io::{prelude::*, ErrorKind},
+    path::{Path, PathBuf},
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use cosm_utils::{chain::error::ChainError, prelude::*};
+use cosmrs::proto::{
+    cosmos::base::query::v1beta1::PageRequest,
+    cosmwasm::wasm::v1::{
+        QueryCodeRequest, QueryCodeResponse, QueryCodesRequest, QueryCodesResponse,
+    },
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tendermint_rpc::HttpClient;
+
+use crate::{file::CodeProvenance, settings::WorkspaceSettings};
+
+/// Returns the hex encoded sha256 of a wasm file.
+/// This is the same checksum the chain stores as the `data_hash` of the code.
+pub fn wasm_checksum(path: &Path) -> anyhow::Result<String> {
+    let wasm = std::fs::read(path)?;
+    Ok(hex::encode(Sha256::digest(wasm)))
+}
+
+/// Describes the artifacts of a contract that is being uploaded by `stored_by`.
+pub fn code_provenance(
+    settings: &WorkspaceSettings,
+    bin_name: &str,
+    checksum: &str,
+    stored_by: &str,
+) -> anyhow::Result<CodeProvenance> {
+    let artifact = settings.artifacts_dir.join(bin_name);
+    let (git_commit, git_dirty) = git_revision(settings);
+    Ok(CodeProvenance {
+        checksum: checksum.to_string(),
+        optimized_size: artifact.with_extension("wasm").metadata()?.len(),
+        gzipped_size: artifact.with_extension("wasm.gz").metadata()?.len(),
+        git_commit,
+        git_dirty,
+        stored_at: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+        stored_by: stored_by.to_string(),
+    })
+}
+
+/// Returns the current commit of the workspace and whether it has uncommitted changes.
+/// Both are `None` if the workspace is not a git repository.
+pub(crate) fn git_revision(settings: &WorkspaceSettings) -> (Option<String>, Option<bool>) {
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .current_dir(&settings.workspace_root)
+            .args(args)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+    let commit = git(&["rev-parse", "HEAD"]);
+    let dirty = commit
+        .as_ref()
+        .and(git(&["status", "--porcelain"]))
+        .map(|status| !status.is_empty());
+    (commit, dirty)
+}
+
+/// Codespace of the errors defined by wasmd
+const WASM_CODESPACE: &str = "wasm";
+/// `ErrNotFound` of wasmd, returned for missing code before `ErrNoSuchCodeFn` existed
+const ERR_NOT_FOUND: u32 = 8;
+/// `ErrNoSuchCodeFn` of wasmd
+const ERR_NO_SUCH_CODE: u32 = 22;
+
+/// Queries the checksum of code that has already been stored on chain.
+/// Returns `None` if the code id does not exist.
+pub async fn query_code_checksum(
+    client: &HttpClient,
+    code_id: u64,
+) -> anyhow::Result<Option<String>> {
+    let res = client
+        .query::<_, QueryCodeResponse>(QueryCodeRequest { code_id }, "/cosmwasm.wasm.v1.Query/Code")
+        .await;
+    match res {
+        Ok(res) => Ok(res.code_info.map(|x| hex::encode(x.data_hash))),
+        Err(ChainError::AbciQuery { res })
+            if res.codespace == WASM_CODESPACE
+                && matches!(res.code.value(), ERR_NOT_FOUND | ERR_NO_SUCH_CODE) =>
+        {
+            Ok(None)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Finds the code id of the most recently stored code with the given checksum.
+/// Codes are searched from newest to oldest, a page at a time,
+/// since code is usually looked up soon after it is stored.
+pub async fn find_code_id(client: &HttpClient, checksum: &str) -> anyhow::Result<Option<u64>> {
+    let mut key = vec![];
+    loop {
+        let res = client
+            .query::<_, QueryCodesResponse>(
+                QueryCodesRequest {
+                    pagination: Some(PageRequest {
+                        key,
+                        offset: 0,
+                        limit: 100,
+                        count_total: false,
+                        reverse: true,
+                    }),
+                },
+                "/cosmwasm.wasm.v1.Query/Codes",
+            )
+            .await?;
+        if let Some(info) = res
+            .code_infos
+            .into_iter()
+            .find(|x| hex::encode(&x.data_hash) == checksum)
+        {
+            return Ok(Some(info.code_id));
+        }
+        match res.pagination {
+            Some(page) if !page.next_key.is_empty() => key = page.next_key,
+            _ => return Ok(None),
+        }
+    }
+}
+
+/// A per chain cache of the code ids that wasm-deploy has stored, keyed by checksum.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CodeCache {
+    pub chain_id: String,
+    pub codes: BTreeMap<String, u64>,
+    /// Where the cached code came from, for contracts that switch to it on a cache hit
+    #[serde(default)]
+    pub provenances: BTreeMap<String, CodeProvenance>,
+}
+
+impl CodeCache {
+    /// Default path is `.wasm-deploy/code_cache/<chain_id>.json`
+    pub fn path(settings: &WorkspaceSettings, chain_id: &str) -> PathBuf {
+        settings
+            .config_path
+            .parent()
+            .expect("Invalid CONFIG_PATH")
+            .join("code_cache")
+            .join(format!("{chain_id}.json"))
+    }
+
+    pub fn load(settings: &WorkspaceSettings, chain_id: &str) -> anyhow::Result<CodeCache> {
+        match std::fs::read(CodeCache::path(settings, chain_id)) {
+            Ok(serialized) => Ok(serde_json::from_slice(&serialized)?),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(CodeCache {
+                chain_id: chain_id.to_string(),
+                ..Default::default()
+            }),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, settings: &WorkspaceSettings) -> anyhow::Result<()> {
+        let path = CodeCache::path(settings, &self.chain_id);
+        create_dir_all(path.parent().expect("Invalid code cache path"))?;
+        let mut file = OpenOptions::new()
+            .truncate(true)
+            .write(true)
+            .create(true)
+            .open(path)?;
+        let serialized = serde_json::to_vec_pretty(self)?;
+        file.write_all(&serialized)?;
+        Ok(())
+    }
+
+    /// Finds a code id on chain whose checksum matches.
+    /// Candidates are taken from the cache and from `known_code_id`,
+    /// and each one is verified against the chain before being returned.
+    /// Otherwise the latest codes on chain are searched, for code stored by someone else.
+    pub async fn find(
+        &self,
+        client: &HttpClient,
+        checksum: &str,
+        known_code_id: Option<u64>,
+    ) -> anyhow::Result<Option<u64>> {
+        let candidates = self
+            .codes
+            .get(checksum)
+            .copied()
+            .into_iter()
+            .chain(known_code_id);
+        for code_id in candidates {
+            if query_code_checksum(client, code_id).await?.as_deref() == Some(checksum) {
+                return Ok(Some(code_id));
+            }
+        }
+        find_code_id(client, checksum).await
+    }
+}
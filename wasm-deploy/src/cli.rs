@@ -1,8 +1,8 @@
 use clap::{Parser, Subcommand};
-use std::fmt::Debug;
+use std::{fmt::Debug, path::PathBuf};
 use strum::IntoEnumIterator;
 
-use crate::contract::Deploy;
+use crate::{contract::Deploy, proposal::ProposalConfig, tx::TxConfig};
 
 #[derive(Parser, Clone, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -17,6 +17,13 @@ where
     /// Add additional args to cargo build
     #[arg(long, required = false)]
     pub cargo_args: Vec<String>,
+
+    #[command(flatten)]
+    pub tx: TxConfig,
+
+    /// Signs every tx with this key instead of the keys of the env
+    #[arg(long, global = true)]
+    pub key: Option<String>,
 }
 
 #[derive(Parser, Clone, Debug)]
@@ -87,6 +94,27 @@ where
         /// Deploys but does not recompile first
         #[arg(short, long, required = false)]
         no_build: bool,
+
+        /// Skips the stages recorded in the journal and continues from the last failure
+        #[arg(long, required = false)]
+        resume: bool,
+
+        /// Simulates the transactions instead of broadcasting them
+        #[arg(long, required = false)]
+        dry_run: bool,
+
+        /// Runs against these envs instead of the active one
+        #[arg(
+            long,
+            use_value_delimiter = true,
+            value_delimiter = ',',
+            conflicts_with = "all_envs"
+        )]
+        envs: Vec<String>,
+
+        /// Runs against every env
+        #[arg(long, required = false)]
+        all_envs: bool,
     },
 
     /// Modify deployment environments
@@ -120,8 +148,34 @@ where
         /// Name of the contract
         #[arg(short, long, use_value_delimiter=true, value_delimiter=',', default_values=get_all::<C>())]
         contracts: Vec<C>,
+
+        /// Simulates the transactions instead of broadcasting them
+        #[arg(long, required = false)]
+        dry_run: bool,
+
+        #[command(flatten)]
+        proposal: ProposalConfig,
+
+        /// Runs against these envs instead of the active one
+        #[arg(
+            long,
+            use_value_delimiter = true,
+            value_delimiter = ',',
+            conflicts_with = "all_envs"
+        )]
+        envs: Vec<String>,
+
+        /// Runs against every env
+        #[arg(long, required = false)]
+        all_envs: bool,
     },
 
+    /// Updates who may instantiate the stored code of the contracts
+    UpdateInstantiateConfig {
+        /// Name of the contract
+        #[arg(short, long, use_value_delimiter=true, value_delimiter=',', default_values=get_all::<C>())]
+        contracts: Vec<C>,
+    },
     /// Instantiates a contract using the preprogrammed messages
     #[command(visible_alias = "i")]
     Instantiate {
@@ -132,8 +186,21 @@ where
         /// Interactive mode
         #[arg(short, long, required = false)]
         interactive: bool,
+
+        /// Simulates the transactions instead of broadcasting them
+        #[arg(long, required = false)]
+        dry_run: bool,
+
+        #[command(flatten)]
+        proposal: ProposalConfig,
     },
 
+    /// Previews the addresses that salted contracts will be instantiated at
+    PredictAddress {
+        /// Name of the contract
+        #[arg(short, long, use_value_delimiter=true, value_delimiter=',', default_values=get_all::<C>())]
+        contracts: Vec<C>,
+    },
     /// Migrates contracts
     #[command(visible_alias = "m")]
     Migrate {
@@ -144,13 +211,98 @@ where
         /// Interactive mode
         #[arg(short, long, required = false)]
         interactive: bool,
+
+        /// Simulates the transactions instead of broadcasting them
+        #[arg(long, required = false)]
+        dry_run: bool,
+
+        #[command(flatten)]
+        proposal: ProposalConfig,
+
+        /// Runs against these envs instead of the active one
+        #[arg(
+            long,
+            use_value_delimiter = true,
+            value_delimiter = ',',
+            conflicts_with = "all_envs"
+        )]
+        envs: Vec<String>,
+
+        /// Runs against every env
+        #[arg(long, required = false)]
+        all_envs: bool,
+    },
+
+    /// Writes the code ids stored by a passed governance proposal to the config
+    IngestProposal {
+        /// Name of the contract
+        #[arg(short, long, use_value_delimiter=true, value_delimiter=',', default_values=get_all::<C>())]
+        contracts: Vec<C>,
+
+        /// Id of the proposal
+        proposal_id: u64,
+    },
+
+    /// Migrates a contract back to an earlier code id
+    Rollback {
+        /// Name of the contract
+        #[arg(short, long)]
+        contract: C,
+        /// Code id to migrate back to, defaults to the one before the current code id
+        #[arg(long)]
+        to: Option<u64>,
+        /// Migrate msg as JSON, defaults to the preprogrammed rollback msg
+        #[arg(short, long)]
+        msg: Option<String>,
+    },
+    /// Changes or removes the admin of contracts
+    #[command(arg_required_else_help = true)]
+    Admin {
+        #[command(subcommand)]
+        command: AdminCommands<C>,
+    },
+    /// Grants the deployer key the authz permissions to deploy on behalf of the authz granter.
+    /// Sign with the granter key using --key, or generate the tx with --from
+    Grant {
+        /// Address that receives the grant, defaults to the address of the key bound to the
+        /// deployer role, or the env key
+        #[arg(long)]
+        grantee: Option<String>,
+        /// When the grant expires, as an RFC 3339 timestamp such as 2027-01-01T00:00:00Z
+        #[arg(long)]
+        expiration: Option<String>,
+    },
+    /// Signs or broadcasts tx files, such as those written with --generate-only
+    #[command(arg_required_else_help = true)]
+    Tx {
+        #[command(subcommand)]
+        command: TxCommands,
+    },
+    /// Runs the verify queries of contracts and checks their responses
+    Verify {
+        /// Name of the contract
+        #[arg(short, long, use_value_delimiter=true, value_delimiter=',', default_values=get_all::<C>())]
+        contracts: Vec<C>,
     },
 
+    /// Shows the txs broadcast in the active env
+    History {
+        /// Only show txs that touched these contracts
+        #[arg(short, long, use_value_delimiter = true, value_delimiter = ',')]
+        contracts: Vec<String>,
+        /// Only show txs from this stage, e.g. instantiate or execute
+        #[arg(short, long)]
+        stage: Option<String>,
+    },
     /// Sets the config of a contract
     SetConfig {
         /// Name of the contract
         #[arg(short, long, use_value_delimiter=true, value_delimiter=',', default_values=get_all::<C>())]
         contracts: Vec<C>,
+
+        /// Simulates the transactions instead of broadcasting them
+        #[arg(long, required = false)]
+        dry_run: bool,
     },
 
     /// Executes a contract
@@ -191,6 +343,52 @@ where
         /// Name of the contract
         #[arg(short, long, use_value_delimiter=true, value_delimiter=',', default_values=get_all::<C>())]
         contracts: Vec<C>,
+
+        /// Simulates the transactions instead of broadcasting them
+        #[arg(long, required = false)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand, Clone, Debug)]
+#[clap(rename_all = "snake_case")]
+pub enum AdminCommands<C>
+where
+    C: Deploy + Clone,
+{
+    /// Sets a new admin for the contracts
+    Update {
+        /// Name of the contract
+        #[arg(short, long, use_value_delimiter=true, value_delimiter=',', default_values=get_all::<C>())]
+        contracts: Vec<C>,
+        /// Address of the new admin, defaults to the admin override of the env
+        #[arg(short, long)]
+        admin: Option<String>,
+    },
+    /// Removes the admin of the contracts
+    Clear {
+        /// Name of the contract
+        #[arg(short, long, use_value_delimiter=true, value_delimiter=',', default_values=get_all::<C>())]
+        contracts: Vec<C>,
+    },
+}
+
+#[derive(Subcommand, Clone, Debug)]
+#[clap(rename_all = "snake_case")]
+pub enum TxCommands {
+    /// Signs a tx file with the key of the env, or the one given with --key.
+    /// The key must be the only signer of the tx
+    Sign {
+        /// Path of the unsigned tx file
+        file: PathBuf,
+        /// Where to write the signed tx, defaults to the tx file with a `.signed.json` extension
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Broadcasts a signed tx file
+    Broadcast {
+        /// Path of the signed tx file
+        file: PathBuf,
     },
 }
 
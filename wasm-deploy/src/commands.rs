@@ -1,5 +1,5 @@
 use std::ffi::OsString;
-use std::{env, process::Command, str::FromStr, sync::Arc};
+use std::{collections::BTreeMap, env, future::Future, process::Command, str::FromStr, sync::Arc};
 
 use async_recursion::async_recursion;
 use clap::{CommandFactory, Subcommand};
@@ -11,14 +11,22 @@ use colored::{self, Colorize};
 use colored_json::to_colored_json_auto;
 use cosm_utils::prelude::*;
 use cosm_utils::{
-    chain::{coin::Coin, request::TxOptions},
-    modules::{auth::model::Address, cosmwasm::model::ExecRequest},
+    chain::{coin::Coin, request::TxOptions, tx::RawTx},
+    modules::{
+        auth::model::Address,
+        cosmwasm::model::{ExecRequest, MigrateRequest},
+    },
+};
+use cosmrs::{
+    proto::cosmos::{gov::v1beta1::ProposalStatus, tx::v1beta1::TxRaw},
+    tendermint::Time,
 };
 #[cfg(feature = "wasm_opt")]
 use futures::future::join_all;
-use inquire::{MultiSelect, Select};
+use inquire::{Confirm, MultiSelect, Select};
 use interactive_parse::InteractiveParseObj;
 use log::info;
+use prost::Message;
 use tendermint_rpc::client::CompatMode;
 use tendermint_rpc::{HttpClient, HttpClientUrl};
 #[cfg(feature = "wasm_opt")]
@@ -29,16 +37,34 @@ use wasm_opt::integration::run_from_command_args;
 #[cfg(wasm_cli)]
 use crate::wasm_cli::wasm_cli_import_schemas;
 use crate::{
-    cli::{Cli, Commands},
+    admin::{
+        clear_admin_batch_commit, query_admin, update_admin_batch_commit, ClearAdminRequest,
+        UpdateAdminRequest,
+    },
+    authz::{grant_commit, GRANTED_MSG_TYPES},
+    checksum::{code_provenance, find_code_id, query_code_checksum, wasm_checksum, CodeCache},
+    cli::{AdminCommands, Cli, Commands, TxCommands},
+    context::DeployContext,
     contract::Deploy,
     cw20::{cw20_execute, cw20_instantiate, cw20_send},
     deployment::{execute_deployment, DeploymentStage},
     error::DeployError,
     execute::execute_contract,
-    file::{Config, CONFIG, WORKSPACE_SETTINGS},
-    query::{cw20_query, query_contract},
+    file::{
+        on_contract_chain, CodeIdRecord, Config, ContractInfo, CONFIG, ENV_OVERRIDE, KEY_OVERRIDE,
+        PROPOSAL, TX_OVERRIDES, WORKSPACE_SETTINGS,
+    },
+    history::{print_history, record_tx},
+    instantiate2::instantiate2_address,
+    journal::{Journal, JournalEntry},
+    permissions::{update_instantiate_config_batch_commit, UpdateInstantiateConfigRequest},
+    proposal::{query_proposal, stored_checksums, PROPOSAL_STATUS_PASSED},
+    query::{cw20_query, query_contract, query_contract_info},
     settings::WorkspaceSettings,
-    utils::BIN_NAME,
+    tx::{broadcast, tx_config},
+    tx_file::{msg_to_json, TxFile},
+    utils::{print_res, BIN_NAME},
+    verify::verify,
 };
 use flate2::write::GzEncoder;
 use flate2::Compression;
@@ -50,23 +76,51 @@ use std::path::{Path, PathBuf};
 #[async_recursion(?Send)]
 pub async fn execute_args<C, S>(settings: &WorkspaceSettings, cli: &Cli<C, S>) -> anyhow::Result<()>
 where
-    C: Deploy + Clone,
+    C: Deploy + Clone + Sync,
     S: Subcommand + Clone + Debug,
 {
     info!("Executing args: {:#?}", cli);
     std::env::set_current_dir(settings.workspace_root.clone())?;
     *WORKSPACE_SETTINGS.write().await = Some(Arc::new(settings.clone()));
+    *TX_OVERRIDES.write().await = cli.tx.clone();
+    *KEY_OVERRIDE.write().await = cli.key.clone();
+    *PROPOSAL.write().await = match &cli.command {
+        Commands::StoreCode { proposal, .. }
+        | Commands::Instantiate { proposal, .. }
+        | Commands::Migrate { proposal, .. }
+            if proposal.proposal =>
+        {
+            Some(proposal.clone())
+        }
+        _ => None,
+    };
+    let cargo_args = &cli.cargo_args;
     match &cli.command {
         Commands::Update {} => update::<C, S>(settings).await?,
         Commands::Init {} => init(settings).await?,
-        Commands::Build { contracts } => build(settings, contracts, &cli.cargo_args).await?,
+        Commands::Build { contracts } => build(settings, contracts, cargo_args).await?,
         Commands::Chain { add, delete } => chain(settings, add, delete).await?,
         Commands::Key { add, delete } => key(settings, add, delete).await?,
         Commands::Contract { add, delete } => contract(settings, add, delete).await?,
         Commands::Deploy {
             contracts,
             no_build,
-        } => deploy(settings, contracts, no_build, &cli.cargo_args).await?,
+            resume,
+            dry_run,
+            envs,
+            all_envs,
+        } => match selected_envs(envs, *all_envs).await? {
+            Some(envs) => {
+                if !no_build {
+                    build(settings, contracts, cargo_args).await?;
+                }
+                run_in_envs(&envs, || {
+                    deploy(settings, contracts, &true, *resume, *dry_run, cargo_args)
+                })
+                .await?
+            }
+            None => deploy(settings, contracts, no_build, *resume, *dry_run, cargo_args).await?,
+        },
         Commands::Env {
             add,
             delete,
@@ -74,28 +128,85 @@ where
             id,
         } => execute_env(settings, add, delete, select, id).await?,
         Commands::Schema { contracts } => schemas(contracts)?,
-        Commands::StoreCode { contracts } => store_code(settings, contracts).await?,
+        Commands::StoreCode {
+            contracts,
+            dry_run,
+            envs,
+            all_envs,
+            ..
+        } => match selected_envs(envs, *all_envs).await? {
+            Some(envs) => run_in_envs(&envs, || store_code(settings, contracts, *dry_run)).await?,
+            None => store_code(settings, contracts, *dry_run).await?,
+        },
         Commands::Instantiate {
             contracts,
             interactive,
-        } => instantiate(settings, contracts, *interactive).await?,
+            dry_run,
+            ..
+        } => instantiate(settings, contracts, *interactive, *dry_run).await?,
+        Commands::UpdateInstantiateConfig { contracts } => {
+            update_instantiate_config(contracts).await?
+        }
+        Commands::PredictAddress { contracts } => predict_addresses(settings, contracts).await?,
         Commands::Migrate {
             contracts,
             interactive,
-        } => migrate(settings, contracts, *interactive, &cli.cargo_args).await?,
-        Commands::Execute { contract } => execute_contract(contract).await?,
-        Commands::Cw20Send { contract } => cw20_send(contract).await?,
+            dry_run,
+            envs,
+            all_envs,
+            ..
+        } => match selected_envs(envs, *all_envs).await? {
+            Some(envs) => {
+                build(settings, contracts, cargo_args).await?;
+                run_in_envs(&envs, || {
+                    migrate_built(settings, contracts, *interactive, *dry_run)
+                })
+                .await?
+            }
+            None => migrate(settings, contracts, *interactive, *dry_run, cargo_args).await?,
+        },
+        Commands::Execute { contract } => {
+            on_contract_chain(&contract.to_string(), execute_contract(contract)).await??
+        }
+        Commands::Cw20Send { contract } => {
+            on_contract_chain(&contract.to_string(), cw20_send(contract)).await??
+        }
         Commands::Cw20Execute {} => cw20_execute().await?,
         Commands::Cw20Query {} => {
             cw20_query().await?;
         }
         Commands::Cw20Instantiate {} => cw20_instantiate().await?,
-        Commands::ExecutePayload { contract, payload } => custom_execute(contract, payload).await?,
-        Commands::SetConfig { contracts } => set_config(settings, contracts).await?,
+        Commands::ExecutePayload { contract, payload } => {
+            on_contract_chain(&contract.to_string(), custom_execute(contract, payload)).await??
+        }
+        Commands::IngestProposal {
+            contracts,
+            proposal_id,
+        } => ingest_proposal(settings, contracts, *proposal_id).await?,
+        Commands::Rollback { contract, to, msg } => {
+            on_contract_chain(
+                &contract.to_string(),
+                rollback(settings, contract, *to, msg.as_deref()),
+            )
+            .await??
+        }
+        Commands::Admin { command } => admin(command).await?,
+        Commands::Grant {
+            grantee,
+            expiration,
+        } => grant(grantee.as_deref(), expiration.as_deref()).await?,
+        Commands::Tx { command } => tx(command).await?,
+        Commands::Verify { contracts } => verify(contracts).await?,
+        Commands::History { contracts, stage } => {
+            print_history(settings, &*CONFIG.read().await, contracts, stage.as_deref()).await?
+        }
+        Commands::SetConfig { contracts, dry_run } => {
+            set_config(settings, contracts, *dry_run).await?
+        }
         Commands::Query { contract } => {
-            query_contract(contract).await?;
+            on_contract_chain(&contract.to_string(), query_contract(contract)).await??;
         }
-        Commands::SetUp { contracts } => set_up(settings, contracts).await?,
+        Commands::SetUp { contracts, dry_run } => set_up(settings, contracts, *dry_run).await?,
         Commands::Custom(..) => {}
     };
     Ok(())
@@ -209,20 +320,181 @@ pub async fn execute_env(
 
 pub async fn deploy(
     settings: &WorkspaceSettings,
-    contracts: &[impl Deploy],
+    contracts: &[impl Deploy + Clone],
     no_build: &bool,
+    resume: bool,
+    dry_run: bool,
     cargo_args: &[String],
 ) -> anyhow::Result<()> {
+    let config = CONFIG.read().await;
+    let env_id = config.get_active_env()?.env_id.clone();
+    // Generated txs have not been sent, so there is nothing to journal or verify
+    let dry_run = dry_run || tx_config(&config).await?.generate_only;
+    drop(config);
+    let mut journal = if resume {
+        Journal::load(settings, &env_id)?
+    } else {
+        if !dry_run {
+            Journal::clear(settings, &env_id)?;
+        }
+        Journal::new(&env_id)
+    };
+
     if !no_build {
-        build(settings, contracts, cargo_args).await?;
+        let remaining = remaining_for_stage(&journal, "build", contracts);
+        if !remaining.is_empty() {
+            build(settings, &remaining, cargo_args).await?;
+            if !dry_run {
+                for contract in &remaining {
+                    journal.record(JournalEntry {
+                        stage: "build".to_string(),
+                        contract: contract.name(),
+                        tx_hash: None,
+                        result: None,
+                    });
+                }
+                journal.save(settings)?;
+            }
+        }
+    }
+
+    let remaining = remaining_for_stage(&journal, "store_code", contracts);
+    if !remaining.is_empty() {
+        execute_deployment(
+            settings,
+            &remaining,
+            DeploymentStage::StoreCode,
+            dry_run,
+            Some(&mut journal),
+        )
+        .await?;
+    }
+
+    for stage in [
+        DeploymentStage::Instantiate { interactive: false },
+        DeploymentStage::ExternalInstantiate,
+        DeploymentStage::SetConfig,
+        DeploymentStage::SetUp,
+    ] {
+        let remaining = remaining_for_stage(&journal, &stage.to_string(), contracts);
+        if !remaining.is_empty() {
+            execute_deployment(settings, &remaining, stage, dry_run, Some(&mut journal)).await?;
+        }
+    }
+
+    if !dry_run {
+        verify(contracts).await?;
+        Journal::clear(settings, &env_id)?;
     }
-    store_code(settings, contracts).await?;
-    instantiate(settings, contracts, false).await?;
-    set_config(settings, contracts).await?;
-    set_up(settings, contracts).await?;
     Ok(())
 }
 
+/// Returns the envs picked with `--envs` or `--all-envs`, or `None` to use the active env.
+pub async fn selected_envs(envs: &[String], all_envs: bool) -> anyhow::Result<Option<Vec<String>>> {
+    let config = CONFIG.read().await;
+    if all_envs {
+        return Ok(Some(config.envs.iter().map(|x| x.env_id.clone()).collect()));
+    }
+    if envs.is_empty() {
+        return Ok(None);
+    }
+    for env_id in envs {
+        if !config.envs.iter().any(|x| &x.env_id == env_id) {
+            return Err(DeployError::EnvNotFound.into());
+        }
+    }
+    Ok(Some(envs.to_vec()))
+}
+
+/// Runs `run` once for every env in `envs`, with that env as the active one, and prints a summary.
+/// Envs on the same chain run one after another, envs on different chains run concurrently,
+/// each chain on its own thread.
+pub async fn run_in_envs<F, Fut>(envs: &[String], run: F) -> anyhow::Result<()>
+where
+    F: Fn() -> Fut + Sync,
+    Fut: Future<Output = anyhow::Result<()>>,
+{
+    let config = CONFIG.read().await;
+    let mut chains: Vec<(String, Vec<String>)> = vec![];
+    for env_id in envs {
+        let chain_id = config
+            .envs
+            .iter()
+            .find(|x| &x.env_id == env_id)
+            .ok_or(DeployError::EnvNotFound)?
+            .chain_id
+            .clone();
+        match chains.iter_mut().find(|(x, _)| *x == chain_id) {
+            Some((_, chain_envs)) => chain_envs.push(env_id.clone()),
+            None => chains.push((chain_id, vec![env_id.clone()])),
+        }
+    }
+    drop(config);
+
+    let run = &run;
+    let mut results = std::thread::scope(|scope| {
+        let handles = chains
+            .iter()
+            .map(|(chain_id, chain_envs)| {
+                scope.spawn(move || {
+                    let runtime = tokio::runtime::Builder::new_current_thread()
+                        .enable_all()
+                        .build()?;
+                    let mut results = vec![];
+                    for env_id in chain_envs {
+                        let result = runtime.block_on(ENV_OVERRIDE.scope(env_id.clone(), run()));
+                        results.push((env_id.clone(), chain_id.clone(), result));
+                    }
+                    anyhow::Ok(results)
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut results = vec![];
+        for handle in handles {
+            let chain_results = handle
+                .join()
+                .unwrap_or_else(|e| std::panic::resume_unwind(e))?;
+            results.extend(chain_results);
+        }
+        anyhow::Ok(results)
+    })?;
+    results.sort_by_key(|(env_id, _, _)| envs.iter().position(|x| x == env_id));
+
+    println!("{}", "Summary".bold());
+    for (env_id, chain_id, result) in &results {
+        match result {
+            Ok(()) => println!("{env_id} ({chain_id}): {}", "ok".green()),
+            Err(e) => println!("{env_id} ({chain_id}): {} {e}", "failed".red()),
+        }
+    }
+    let failed = results.iter().filter(|(_, _, x)| x.is_err()).count();
+    if failed == 0 {
+        Ok(())
+    } else {
+        Err(DeployError::EnvsFailed {
+            failed,
+            total: results.len(),
+        }
+        .into())
+    }
+}
+
+fn remaining_for_stage<C: Deploy + Clone>(
+    journal: &Journal,
+    stage: &str,
+    contracts: &[C],
+) -> Vec<C> {
+    let remaining = journal.remaining(stage, contracts);
+    if remaining.len() < contracts.len() {
+        println!(
+            "Skipping {} for {} contract(s) already in the journal",
+            stage,
+            contracts.len() - remaining.len()
+        );
+    }
+    remaining
+}
+
 pub async fn update<C, S>(settings: &WorkspaceSettings) -> anyhow::Result<()>
 where
     C: Deploy + Clone,
@@ -474,45 +746,579 @@ pub fn set_execute_permissions(
 
 pub async fn store_code(
     settings: &WorkspaceSettings,
-    contracts: &[impl Deploy],
+    contracts: &[impl Deploy + Clone],
+    dry_run: bool,
 ) -> anyhow::Result<()> {
-    let chunk_size = CONFIG.read().await.settings.store_code_chunk_size;
-    let chunks = contracts.chunks(chunk_size);
-    for chunk in chunks {
-        execute_deployment(settings, chunk, DeploymentStage::StoreCode).await?;
-    }
+    execute_deployment(
+        settings,
+        contracts,
+        DeploymentStage::StoreCode,
+        dry_run,
+        None,
+    )
+    .await?;
     Ok(())
 }
 
 pub async fn instantiate(
     settings: &WorkspaceSettings,
-    contracts: &[impl Deploy],
+    contracts: &[impl Deploy + Clone],
     interactive: bool,
+    dry_run: bool,
 ) -> anyhow::Result<()> {
     execute_deployment(
         settings,
         contracts,
         DeploymentStage::Instantiate { interactive },
+        dry_run,
+        None,
+    )
+    .await?;
+    execute_deployment(
+        settings,
+        contracts,
+        DeploymentStage::ExternalInstantiate,
+        dry_run,
+        None,
     )
     .await?;
-    execute_deployment(settings, contracts, DeploymentStage::ExternalInstantiate).await?;
 
     Ok(())
 }
 
-pub async fn migrate(
+/// Writes the code ids stored by a passed governance proposal to the contracts whose built code it stored.
+pub async fn ingest_proposal(
+    settings: &WorkspaceSettings,
+    contracts: &[impl Deploy],
+    proposal_id: u64,
+) -> anyhow::Result<()> {
+    let chain_info = CONFIG.read().await.get_active_chain_info()?.clone();
+    let client = HttpClient::get_persistent_compat(chain_info.rpc_endpoint.as_str()).await?;
+    let proposal = query_proposal(&client, proposal_id).await?;
+    if proposal.status != PROPOSAL_STATUS_PASSED {
+        return Err(DeployError::ProposalNotPassed {
+            proposal_id,
+            status: ProposalStatus::from_i32(proposal.status)
+                .map(|x| x.as_str_name().to_string())
+                .unwrap_or(proposal.status.to_string()),
+        }
+        .into());
+    }
+    let stored = stored_checksums(&proposal)?;
+
+    let mut cache = CodeCache::load(settings, &chain_info.cfg.chain_id)?;
+    let mut config = CONFIG.write().await;
+    let mut ingested = 0;
+    // Searching the stored codes can take many pages, so every checksum is only searched once
+    let mut code_ids = BTreeMap::new();
+    for contract in contracts {
+        let checksum = wasm_checksum(
+            &settings
+                .artifacts_dir
+                .join(format!("{}.wasm", contract.bin_name())),
+        )?;
+        let Some((_, stored_by)) = stored.iter().find(|(x, _)| *x == checksum) else {
+            continue;
+        };
+        let code_id = match code_ids.get(&checksum) {
+            Some(code_id) => *code_id,
+            None => find_code_id(&client, &checksum)
+                .await?
+                .ok_or(DeployError::CodeIdNotFound)?,
+        };
+        code_ids.insert(checksum.clone(), code_id);
+        println!(
+            "Code for {} is stored with code id {}",
+            contract.name(),
+            code_id
+        );
+        cache.codes.insert(checksum.clone(), code_id);
+        let provenance = code_provenance(settings, &contract.bin_name(), &checksum, stored_by)?;
+        cache
+            .provenances
+            .insert(checksum.clone(), provenance.clone());
+        match config.get_contract_mut(&contract.to_string()) {
+            Ok(contract_info) => {
+                contract_info.code_id = Some(code_id);
+                contract_info.provenance = Some(provenance);
+            }
+            Err(_) => {
+                config.add_contract_from(ContractInfo {
+                    name: contract.name(),
+                    addr: None,
+                    code_id: Some(code_id),
+                    chain_id: None,
+                    provenance: Some(provenance),
+                    code_id_history: vec![],
+                })?;
+            }
+        }
+        ingested += 1;
+    }
+    if ingested == 0 {
+        println!("No built contract matches the code stored by proposal {proposal_id}");
+    }
+    cache.save(settings)?;
+    config.save(settings)?;
+    Ok(())
+}
+
+/// Migrates a contract back to an earlier code id and records it in the code id history.
+/// The migrate msg is taken from `msg`, then `Deploy::rollback_msg`, and is otherwise parsed interactively.
+pub async fn rollback(
+    settings: &WorkspaceSettings,
+    contract: &impl Deploy,
+    to: Option<u64>,
+    msg: Option<&str>,
+) -> anyhow::Result<()> {
+    let config = CONFIG.read().await;
+    let chain_info = config.get_active_chain_info()?.clone();
+    let key = config.get_role_key(Some("admin")).await?;
+    let tx_config = tx_config(&config).await?;
+    let client = HttpClient::get_persistent_compat(chain_info.rpc_endpoint.as_str()).await?;
+    let contract_info = config.get_contract(&contract.to_string())?.clone();
+    drop(config);
+    let ctx = DeployContext::load(false, Some("admin")).await?;
+    let contract_addr = contract_info
+        .addr
+        .clone()
+        .ok_or(DeployError::AddrNotFound {
+            name: contract.name(),
+        })?;
+    let current_code_id = query_contract_info(&client, &contract_addr)
+        .await?
+        .ok_or(DeployError::AddrNotFound {
+            name: contract.name(),
+        })?
+        .code_id;
+    let code_id = match to {
+        Some(code_id) => code_id,
+        None => contract_info
+            .code_id_history
+            .iter()
+            .rev()
+            .map(|x| x.code_id)
+            .find(|code_id| *code_id != current_code_id)
+            .ok_or(DeployError::RollbackTargetNotFound {
+                name: contract.name(),
+            })?,
+    };
+
+    let msg: serde_json::Value = match msg {
+        Some(msg) => serde_json::from_str(msg)?,
+        None => match contract.rollback_msg(&ctx, code_id) {
+            Some(msg) => serde_json::to_value(msg)?,
+            None => serde_json::to_value(contract.migrate()?)?,
+        },
+    };
+    println!(
+        "Rolling back {} from code id {} to {}",
+        contract.name().bold(),
+        current_code_id.to_string().yellow(),
+        code_id.to_string().green()
+    );
+    println!("{}", to_colored_json_auto(&msg)?);
+    if !Confirm::new("Migrate the contract?")
+        .with_default(false)
+        .prompt()?
+    {
+        return Ok(());
+    }
+
+    let sender_addr = tx_config.sender_addr(&key, &chain_info.cfg).await?;
+    let signer_addr = tx_config.signer_addr(&key, &chain_info.cfg).await?;
+    let req = MigrateRequest {
+        msg: msg.clone(),
+        address: Address::from_str(&contract_addr)?,
+        new_code_id: code_id,
+    };
+    let Some(response) = broadcast(
+        &client,
+        &chain_info.cfg,
+        vec![req.to_proto(sender_addr)?],
+        &key,
+        &tx_config,
+    )
+    .await?
+    else {
+        return Ok(());
+    };
+    record_tx(
+        &*CONFIG.read().await,
+        "rollback",
+        &signer_addr,
+        &[(contract.name(), msg.clone())],
+        &response,
+    )
+    .await?;
+    let mut config = CONFIG.write().await;
+    config
+        .get_contract_mut(&contract.to_string())?
+        .push_code_id(
+            current_code_id,
+            CodeIdRecord {
+                code_id,
+                migrate_msg: Some(msg),
+                tx_hash: Some(response.hash.to_string()),
+            },
+        );
+    config.save(settings)?;
+    print_res(response);
+    Ok(())
+}
+
+/// Updates or clears the admin of contracts after showing the current admin of each one.
+pub async fn admin<C: Deploy + Clone>(command: &AdminCommands<C>) -> anyhow::Result<()> {
+    let config = CONFIG.read().await;
+    let chain_info = config.get_active_chain_info()?.clone();
+    let key = config.get_role_key(Some("admin")).await?;
+    let tx_config = tx_config(&config).await?;
+    let client = HttpClient::get_persistent_compat(chain_info.rpc_endpoint.as_str()).await?;
+    let (contracts, new_admin) = match command {
+        AdminCommands::Update { contracts, admin } => {
+            let new_admin = match admin {
+                Some(admin) => admin.clone(),
+                None => config
+                    .get_active_env()?
+                    .admin
+                    .clone()
+                    .ok_or(DeployError::AdminNotFound)?,
+            };
+            (contracts, Some(Address::from_str(&new_admin)?))
+        }
+        AdminCommands::Clear { contracts } => (contracts, None),
+    };
+
+    let mut addrs = vec![];
+    let mut sent_msgs = vec![];
+    for contract in contracts {
+        let addr = Address::from_str(config.get_contract_addr(&contract.to_string())?)?;
+        let current = query_admin(&client, addr.as_ref()).await?;
+        println!("{}", contract.name().bold());
+        println!(
+            "  current admin: {}",
+            current.unwrap_or("none".into()).yellow()
+        );
+        println!(
+            "  new admin: {}",
+            new_admin
+                .as_ref()
+                .map(|x| x.to_string())
+                .unwrap_or("none".into())
+                .green()
+        );
+        sent_msgs.push((
+            contract.name(),
+            serde_json::json!({ "contract": addr, "new_admin": new_admin }),
+        ));
+        addrs.push(addr);
+    }
+    drop(config);
+    let stage = match new_admin {
+        Some(_) => "update_admin",
+        None => "clear_admin",
+    };
+
+    if !Confirm::new("Apply these admin changes?")
+        .with_default(false)
+        .prompt()?
+    {
+        return Ok(());
+    }
+
+    let Some(response) = (match new_admin {
+        Some(new_admin) => {
+            let reqs = addrs
+                .into_iter()
+                .map(|contract_addr| UpdateAdminRequest {
+                    contract_addr,
+                    new_admin: new_admin.clone(),
+                })
+                .collect();
+            update_admin_batch_commit(&client, &chain_info.cfg, reqs, &key, &tx_config).await?
+        }
+        None => {
+            let reqs = addrs
+                .into_iter()
+                .map(|contract_addr| ClearAdminRequest { contract_addr })
+                .collect();
+            clear_admin_batch_commit(&client, &chain_info.cfg, reqs, &key, &tx_config).await?
+        }
+    }) else {
+        return Ok(());
+    };
+    let signer_addr = tx_config.signer_addr(&key, &chain_info.cfg).await?;
+    record_tx(
+        &*CONFIG.read().await,
+        stage,
+        &signer_addr,
+        &sent_msgs,
+        &response,
+    )
+    .await?;
+    print_res(response);
+    Ok(())
+}
+
+/// Grants the deployer key every msg type it needs to deploy on behalf of the authz granter of the env.
+pub async fn grant(grantee: Option<&str>, expiration: Option<&str>) -> anyhow::Result<()> {
+    let config = CONFIG.read().await;
+    let chain_info = config.get_active_chain_info()?.clone();
+    let env = config.get_active_env()?;
+    // Signed by the granter, which is picked with --key or --from
+    let key = config.get_active_key().await?;
+    let tx_config = tx_config(&config).await?;
+    let client = HttpClient::get_persistent_compat(chain_info.rpc_endpoint.as_str()).await?;
+    let granter = Address::from_str(
+        tx_config
+            .authz_granter
+            .as_deref()
+            .ok_or(DeployError::AuthzGranterNotFound)?,
+    )?;
+    let grantee = match grantee {
+        Some(grantee) => Address::from_str(grantee)?,
+        // The key that deploys, even though the tx is signed with the granter key given with --key
+        None => {
+            config
+                .get_key(env.role_key_name(Some(DeploymentStage::StoreCode.default_role())))
+                .await?
+                .to_addr(&chain_info.cfg.prefix, &chain_info.cfg.derivation_path)
+                .await?
+        }
+    };
+    let expiration = expiration.map(Time::parse_from_rfc3339).transpose()?;
+    let sent_msgs = GRANTED_MSG_TYPES
+        .iter()
+        .map(|msg_type| {
+            (
+                grantee.to_string(),
+                serde_json::json!({ "granter": granter, "msg": msg_type }),
+            )
+        })
+        .collect::<Vec<_>>();
+    let Some(response) = grant_commit(
+        &client,
+        &chain_info.cfg,
+        &granter,
+        &grantee,
+        expiration,
+        &key,
+        &tx_config,
+    )
+    .await?
+    else {
+        return Ok(());
+    };
+    let signer_addr = tx_config.signer_addr(&key, &chain_info.cfg).await?;
+    record_tx(&config, "authz_grant", &signer_addr, &sent_msgs, &response).await?;
+    print_res(response);
+    Ok(())
+}
+
+/// Signs a tx file, or broadcasts a signed one and records it in the history of the active env.
+pub async fn tx(command: &TxCommands) -> anyhow::Result<()> {
+    let config = CONFIG.read().await;
+    let chain_info = config.get_active_chain_info()?.clone();
+    let client = HttpClient::get_persistent_compat(chain_info.rpc_endpoint.as_str()).await?;
+    match command {
+        TxCommands::Sign { file, output } => {
+            let key = config.get_active_key().await?;
+            let tx = TxFile::load(file)?;
+            // Only single signer txs can be signed here, by the account the msgs are sent from
+            let addr = key
+                .to_addr(&chain_info.cfg.prefix, &chain_info.cfg.derivation_path)
+                .await?
+                .to_string();
+            let signers = tx.signers()?;
+            if signers != [addr.clone()] {
+                return Err(DeployError::SignerMismatch {
+                    signers: signers.join(", "),
+                    addr,
+                }
+                .into());
+            }
+            let tx_options = TxOptions {
+                timeout_height: Some(tx.body.timeout_height),
+                fee: Some(tx.fee()?),
+                account: None,
+                memo: tx.body.memo.clone(),
+            };
+            let raw = client
+                .tx_sign(&chain_info.cfg, tx.msgs(), &key, &tx_options)
+                .await?;
+            let signed = TxFile::from_raw(&TxRaw::decode(raw.to_bytes()?.as_slice())?)?;
+            let output = output
+                .clone()
+                .unwrap_or_else(|| file.with_extension("signed.json"));
+            signed.save(&output)?;
+            println!(
+                "Signed with {}, written to {}",
+                key.name.bold(),
+                output.display()
+            );
+        }
+        TxCommands::Broadcast { file } => {
+            let tx = TxFile::load(file)?;
+            let contracts = &config.get_active_env()?.contracts;
+            let mut sent_msgs = vec![];
+            for any in &tx.body.messages {
+                let msg = msg_to_json(any)?;
+                // Named after the contract the msg was sent to, if it is one of ours
+                let name = match msg.get("contract").and_then(|x| x.as_str()) {
+                    Some(addr) => contracts
+                        .iter()
+                        .find(|x| x.addr.as_deref() == Some(addr))
+                        .map(|x| x.name.clone())
+                        .unwrap_or(addr.to_string()),
+                    None => any.type_url.clone(),
+                };
+                sent_msgs.push((name, msg));
+            }
+            // Recorded under the first signer, which is the account that sent the first msg
+            let signers = tx.signers()?;
+            let signer = signers.first().ok_or(DeployError::InvalidTxFile {
+                reason: "it has no signer".into(),
+            })?;
+            let signer_addr = Address::from_str(signer)?;
+            let raw = RawTx::from_bytes(&tx.to_raw().encode_to_vec())?;
+            let response = ClientTxCommit::broadcast_tx_commit(&client, &raw).await?;
+            record_tx(&config, "broadcast", &signer_addr, &sent_msgs, &response).await?;
+            print_res(response);
+        }
+    }
+    Ok(())
+}
+
+/// Applies the instantiate permission of each contract to its stored code id.
+pub async fn update_instantiate_config(contracts: &[impl Deploy]) -> anyhow::Result<()> {
+    let config = CONFIG.read().await;
+    let chain_info = config.get_active_chain_info()?.clone();
+    let key = config.get_role_key(Some("deployer")).await?;
+    let tx_config = tx_config(&config).await?;
+    let client = HttpClient::get_persistent_compat(chain_info.rpc_endpoint.as_str()).await?;
+    let sender_addr = tx_config.sender_addr(&key, &chain_info.cfg).await?;
+    let signer_addr = tx_config.signer_addr(&key, &chain_info.cfg).await?;
+    let mut reqs = vec![];
+    let mut sent_msgs = vec![];
+    for contract in contracts {
+        match contract.instantiate_permission() {
+            Some(permission) => {
+                println!(
+                    "Updating instantiate config for {} to {:?}",
+                    contract.name(),
+                    permission
+                );
+                let code_id = config
+                    .get_contract(&contract.to_string())?
+                    .code_id
+                    .ok_or(DeployError::CodeIdNotFound)?;
+                let req = UpdateInstantiateConfigRequest {
+                    code_id,
+                    permission: permission.to_access_config(&sender_addr)?,
+                };
+                sent_msgs.push((contract.name(), serde_json::to_value(&req)?));
+                reqs.push(req);
+            }
+            None => println!("No instantiate permission for {}", contract.name()),
+        }
+    }
+    if !reqs.is_empty() {
+        let response = update_instantiate_config_batch_commit(
+            &client,
+            &chain_info.cfg,
+            reqs,
+            &key,
+            &tx_config,
+        )
+        .await?;
+        if let Some(response) = response {
+            record_tx(
+                &config,
+                "update_instantiate_config",
+                &signer_addr,
+                &sent_msgs,
+                &response,
+            )
+            .await?;
+            print_res(response);
+        }
+    }
+    Ok(())
+}
+
+/// Prints the address each salted contract will be instantiated at.
+/// The checksum is taken from the stored code if there is one, otherwise from the local artifact.
+pub async fn predict_addresses(
     settings: &WorkspaceSettings,
     contracts: &[impl Deploy],
+) -> anyhow::Result<()> {
+    let config = CONFIG.read().await;
+    let chain_info = config.get_active_chain_info()?.clone();
+    let key = config.get_role_key(Some("deployer")).await?;
+    let client = HttpClient::get_persistent_compat(chain_info.rpc_endpoint.as_str()).await?;
+    let tx_config = tx_config(&config).await?;
+    let creator = tx_config.sender_addr(&key, &chain_info.cfg).await?;
+    println!("creator: {}", creator.to_string().purple());
+    for contract in contracts {
+        let Some(salt) = contract.salt() else {
+            println!("{} has no salt", contract.name());
+            continue;
+        };
+        let code_id = config
+            .get_contract(&contract.to_string())
+            .ok()
+            .and_then(|x| x.code_id);
+        let checksum = match code_id {
+            Some(code_id) => query_code_checksum(&client, code_id).await?,
+            None => None,
+        };
+        let checksum = match checksum {
+            Some(checksum) => checksum,
+            None => wasm_checksum(
+                &settings
+                    .artifacts_dir
+                    .join(format!("{}.wasm", contract.bin_name())),
+            )?,
+        };
+        let addr = instantiate2_address(&hex::decode(&checksum)?, &creator, &salt)?;
+        println!("{}", contract.name().bold());
+        println!("  salt: {}", String::from_utf8_lossy(&salt));
+        println!("  checksum: {checksum}");
+        println!("  address: {}", addr.to_string().green());
+    }
+    Ok(())
+}
+
+pub async fn migrate(
+    settings: &WorkspaceSettings,
+    contracts: &[impl Deploy + Clone],
     interactive: bool,
+    dry_run: bool,
     cargo_args: &[String],
 ) -> anyhow::Result<()> {
     build(settings, contracts, cargo_args).await?;
-    store_code(settings, contracts).await?;
+    migrate_built(settings, contracts, interactive, dry_run).await
+}
+
+/// Stores and migrates contracts that have already been built.
+/// A migrate proposal only migrates, since the code ids it migrates to
+/// must be known when it is submitted, see `ingest_proposal`.
+pub async fn migrate_built(
+    settings: &WorkspaceSettings,
+    contracts: &[impl Deploy + Clone],
+    interactive: bool,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    if PROPOSAL.read().await.is_none() {
+        store_code(settings, contracts, dry_run).await?;
+    }
 
     execute_deployment(
         settings,
         contracts,
         DeploymentStage::Migrate { interactive },
+        dry_run,
+        None,
     )
     .await?;
 
@@ -521,14 +1327,26 @@ pub async fn migrate(
 
 pub async fn set_config(
     settings: &WorkspaceSettings,
-    contracts: &[impl Deploy],
+    contracts: &[impl Deploy + Clone],
+    dry_run: bool,
 ) -> anyhow::Result<()> {
-    execute_deployment(settings, contracts, DeploymentStage::SetConfig).await?;
+    execute_deployment(
+        settings,
+        contracts,
+        DeploymentStage::SetConfig,
+        dry_run,
+        None,
+    )
+    .await?;
     Ok(())
 }
 
-pub async fn set_up(settings: &WorkspaceSettings, contracts: &[impl Deploy]) -> anyhow::Result<()> {
-    execute_deployment(settings, contracts, DeploymentStage::SetUp).await?;
+pub async fn set_up(
+    settings: &WorkspaceSettings,
+    contracts: &[impl Deploy + Clone],
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    execute_deployment(settings, contracts, DeploymentStage::SetUp, dry_run, None).await?;
     Ok(())
 }
 
@@ -554,9 +1372,28 @@ pub async fn custom_execute<C: Deploy>(contract: &C, string: &str) -> anyhow::Re
         address: Address::from_str(&contract_addr)?,
     };
 
-    let response = client
-        .wasm_execute_commit(&chain_info.cfg, req, &key, &TxOptions::default())
-        .await?;
+    let tx_config = tx_config(&config).await?;
+    let sender_addr = tx_config.sender_addr(&key, &chain_info.cfg).await?;
+    let signer_addr = tx_config.signer_addr(&key, &chain_info.cfg).await?;
+    let Some(response) = broadcast(
+        &client,
+        &chain_info.cfg,
+        vec![req.to_proto(sender_addr)?],
+        &key,
+        &tx_config,
+    )
+    .await?
+    else {
+        return Ok(());
+    };
+    record_tx(
+        &config,
+        "execute",
+        &signer_addr,
+        &[(contract.name(), value)],
+        &response,
+    )
+    .await?;
 
     println!(
         "gas wanted: {}, gas used: {}",
@@ -0,0 +1,108 @@
+use cosm_utils::{
+    chain::msg::Msg as ProtoMsg, modules::auth::model::Address, prelude::*,
+    signing_key::key::SigningKey,
+};
+use serde::Serialize;
+use tendermint_rpc::{endpoint::broadcast::tx_commit, HttpClient};
+
+use crate::{
+    error::DeployError,
+    file::{ChainInfo, ContractInfo, Env, CONFIG, PROPOSAL},
+    proposal::ProposalConfig,
+    tx::{broadcast, tx_config, TxConfig},
+};
+
+/// The state of a deployment, handed to the hooks of `Deploy`.
+#[derive(Clone)]
+pub struct DeployContext {
+    /// The active env, including its contract table and signer roles
+    pub env: Env,
+    pub chain_info: ChainInfo,
+    pub client: HttpClient,
+    pub key: SigningKey,
+    /// Address of `key` on the active chain, or the `from` address when generating txs.
+    /// The authority that executes the proposal when msgs are submitted in one
+    pub sender_addr: Address,
+    pub tx_config: TxConfig,
+    /// Whether txs are only simulated
+    pub dry_run: bool,
+    /// Set when the msgs are submitted in a governance proposal instead of being sent
+    pub proposal: Option<ProposalConfig>,
+}
+
+impl DeployContext {
+    /// Builds the context of the active env and chain from the current config,
+    /// signing with the key bound to `role`, or the key of the env.
+    pub async fn load(dry_run: bool, role: Option<&str>) -> anyhow::Result<DeployContext> {
+        let config = CONFIG.read().await;
+        let env = config.get_active_env()?.clone();
+        let chain_info = config.get_active_chain_info()?.clone();
+        let key = config.get_role_key(role).await?;
+        let tx_config = tx_config(&config).await?;
+        drop(config);
+        let client = HttpClient::get_persistent_compat(chain_info.rpc_endpoint.as_str()).await?;
+        let proposal = PROPOSAL.read().await.clone();
+        let sender_addr = match &proposal {
+            Some(proposal) => proposal.authority(&chain_info.cfg.prefix)?,
+            None => tx_config.sender_addr(&key, &chain_info.cfg).await?,
+        };
+        Ok(DeployContext {
+            env,
+            chain_info,
+            client,
+            key,
+            sender_addr,
+            tx_config,
+            dry_run,
+            proposal,
+        })
+    }
+
+    /// Reloads the env, to pick up the addresses and code ids written since the context was built.
+    pub async fn refresh(&mut self) -> anyhow::Result<()> {
+        self.env = CONFIG.read().await.get_active_env()?.clone();
+        Ok(())
+    }
+
+    pub fn contract(&self, name: &str) -> anyhow::Result<&ContractInfo> {
+        self.env
+            .contracts
+            .iter()
+            .find(|x| x.name == name)
+            .ok_or(DeployError::ContractNotFound.into())
+    }
+
+    pub fn addr(&self, name: &str) -> anyhow::Result<String> {
+        Ok(self
+            .contract(name)?
+            .addr
+            .clone()
+            .ok_or(DeployError::AddrNotFound {
+                name: name.to_string(),
+            })?)
+    }
+
+    pub fn code_id(&self, name: &str) -> anyhow::Result<u64> {
+        Ok(self
+            .contract(name)?
+            .code_id
+            .ok_or(DeployError::CodeIdNotFound)?)
+    }
+
+    /// Signs and broadcasts a tx containing `msgs` with the deployment key.
+    /// Returns `None` when the tx was only written to an unsigned tx file.
+    pub async fn broadcast<T>(&self, msgs: Vec<T>) -> anyhow::Result<Option<tx_commit::Response>>
+    where
+        T: ProtoMsg + Serialize + Send + Sync,
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        broadcast(
+            &self.client,
+            &self.chain_info.cfg,
+            msgs,
+            &self.key,
+            &self.tx_config,
+        )
+        .await
+    }
+}
@@ -4,8 +4,14 @@ use std::{
     str::FromStr,
 };
 
-use crate::error::DeployError;
+use crate::{
+    context::DeployContext, deployment::DeploymentStage, error::DeployError, file::ContractInfo,
+    permissions::InstantiatePermission, utils::replace_strings,
+};
+use async_trait::async_trait;
+use cosm_utils::chain::coin::Coin;
 use serde::Serialize;
+use serde_json::Value;
 use strum::{IntoEnumIterator, ParseError};
 
 pub trait Msg: Debug + Send + Sync + erased_serde::Serialize {}
@@ -22,33 +28,112 @@ impl Serialize for dyn Msg {
 }
 
 /// This trait represents a contract that can be deployed.
+/// Implementations that override the stage hooks need `#[async_trait(?Send)]`.
+#[async_trait(?Send)]
 pub trait Deploy: ContractInteractive {
     /// This method gets the preprogrammed instantiate msg for the contract.
-    fn instantiate_msg(&self) -> Option<Box<dyn Msg>> {
+    /// The context holds the addresses and code ids of the contracts deployed so far.
+    fn instantiate_msg(&self, _ctx: &DeployContext) -> Option<Box<dyn Msg>> {
         println!("No instantiate msg for {}", self.name());
         println!("Defaulting to interactive instantiate");
         self.instantiate().ok()
     }
 
+    /// These are the funds sent along with the instantiate msg.
+    fn instantiate_funds(&self) -> Vec<Coin> {
+        vec![]
+    }
+
+    /// This is who may instantiate the code once it has been stored.
+    /// `None` leaves it to the default of the chain.
+    fn instantiate_permission(&self) -> Option<InstantiatePermission> {
+        None
+    }
+
     /// This method gets the preprogrammed migrate msg for the contract.
-    fn migrate_msg(&self) -> Option<Box<dyn Msg>> {
+    /// The context holds the code ids the contracts are migrating to.
+    fn migrate_msg(&self, _ctx: &DeployContext) -> Option<Box<dyn Msg>> {
+        None
+    }
+
+    /// This method gets the preprogrammed migrate msg used to roll the contract back to `code_id`.
+    fn rollback_msg(&self, _ctx: &DeployContext, _code_id: u64) -> Option<Box<dyn Msg>> {
         None
     }
 
     /// This method gets the preprogrammed set config msg for the contract.
-    fn set_config_msg(&self) -> Option<Box<dyn Msg>> {
+    fn set_config_msg(&self, _ctx: &DeployContext) -> Option<Box<dyn Msg>> {
         None
     }
 
+    /// These are the funds sent along with the set config msg.
+    fn set_config_funds(&self) -> Vec<Coin> {
+        vec![]
+    }
+
     /// This method gets the preprogrammed set up for the contract.
-    fn set_up_msgs(&self) -> Vec<Box<dyn Msg>> {
+    fn set_up_msgs(&self, _ctx: &DeployContext) -> Vec<Box<dyn Msg>> {
         vec![]
     }
 
+    /// This method gets the preprogrammed set up along with the funds for each msg.
+    /// Override this instead of `set_up_msgs` when a set up msg needs funds.
+    fn set_up_msgs_with_funds(&self, ctx: &DeployContext) -> Vec<SetUpMsg<Box<dyn Msg>>> {
+        self.set_up_msgs(ctx)
+            .into_iter()
+            .map(|msg| SetUpMsg { msg, funds: vec![] })
+            .collect()
+    }
+
+    /// This method gets the preprogrammed set up along with where each msg is sent.
+    /// Override this instead of `set_up_msgs_with_funds` when a set up msg
+    /// is executed on another contract or address.
+    fn set_up_items(&self, ctx: &DeployContext) -> Vec<SetUpItem<Box<dyn Msg>>> {
+        self.set_up_msgs_with_funds(ctx)
+            .into_iter()
+            .map(SetUpItem::from)
+            .collect()
+    }
+
     /// This method will instantiate an external contract via code_id alongside a local contract.
-    fn external_instantiate_msgs(&self) -> Vec<ExternalInstantiate<Box<dyn Msg>>> {
+    fn external_instantiate_msgs(
+        &self,
+        _ctx: &DeployContext,
+    ) -> Vec<ExternalInstantiate<Box<dyn Msg>>> {
+        vec![]
+    }
+
+    /// This is the signer role that signs the msgs of this contract in the given stage,
+    /// such as `deployer`, `admin` or `operator`. Defaults to the role of the stage.
+    fn signer_role(&self, stage: &DeploymentStage) -> String {
+        stage.default_role().to_string()
+    }
+
+    /// These are the queries run by the verify stage along with the responses they should return.
+    fn verify_queries(&self, _ctx: &DeployContext) -> Vec<VerifyQuery<Box<dyn Msg>>> {
         vec![]
     }
+
+    /// Called before every deployment stage that includes this contract.
+    /// Returning an error stops the deployment before the stage runs.
+    async fn before_stage(
+        &self,
+        _ctx: &DeployContext,
+        _stage: &DeploymentStage,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Called after every deployment stage that includes this contract, with the result of the stage.
+    /// The context holds the addresses and code ids as the stage left them.
+    async fn after_stage(
+        &self,
+        _ctx: &DeployContext,
+        _stage: &DeploymentStage,
+        _result: &anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
 }
 
 pub trait ContractInteractive:
@@ -78,6 +163,18 @@ pub trait ContractInteractive:
     /// It is required when instantiating.
     fn admin(&self) -> String;
 
+    /// These are the contracts that must be instantiated before this one.
+    /// Contracts in the same deployment are instantiated in dependency order.
+    fn depends_on(&self) -> Vec<Self> {
+        vec![]
+    }
+
+    /// When set, the contract is instantiated with MsgInstantiateContract2
+    /// so that its address can be derived before it is instantiated.
+    fn salt(&self) -> Option<Vec<u8>> {
+        None
+    }
+
     /// This method allows instantiating a contract interactively.
     /// interactive-parse should be used to generate the msg.
     fn instantiate(&self) -> anyhow::Result<Box<dyn Msg>> {
@@ -114,6 +211,25 @@ pub struct ExternalInstantiate<T> {
     pub msg: T,
     pub code_id: u64,
     pub name: String,
+    pub funds: Vec<Coin>,
+}
+
+impl<T> ExternalInstantiate<T> {
+    /// Instantiates `code_id` as `name` without funds, see `with_funds`.
+    pub fn new(name: impl Into<String>, code_id: u64, msg: T) -> Self {
+        ExternalInstantiate {
+            msg,
+            code_id,
+            name: name.into(),
+            funds: vec![],
+        }
+    }
+
+    /// Sets the funds sent along with the instantiate msg.
+    pub fn with_funds(mut self, funds: Vec<Coin>) -> Self {
+        self.funds = funds;
+        self
+    }
 }
 
 impl<T> From<ExternalInstantiate<T>> for ExternalInstantiate<Box<dyn Msg>>
@@ -125,6 +241,134 @@ where
             msg: Box::new(msg.msg),
             code_id: msg.code_id,
             name: msg.name,
+            funds: msg.funds,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SetUpMsg<T> {
+    pub msg: T,
+    pub funds: Vec<Coin>,
+}
+
+impl<T> From<SetUpMsg<T>> for SetUpMsg<Box<dyn Msg>>
+where
+    T: Msg + Clone + 'static,
+{
+    fn from(msg: SetUpMsg<T>) -> Self {
+        SetUpMsg {
+            msg: Box::new(msg.msg),
+            funds: msg.funds,
+        }
+    }
+}
+
+/// Where a set up msg is executed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SetUpTarget {
+    /// The contract whose set up it is
+    This,
+    /// Another contract of the env, by name
+    Contract(String),
+    /// A raw address, or a `&name` reference to a contract of the env
+    Addr(String),
+}
+
+impl SetUpTarget {
+    pub fn contract(contract: &impl ContractInteractive) -> SetUpTarget {
+        SetUpTarget::Contract(contract.to_string())
+    }
+
+    /// Returns the address the msg is sent to.
+    /// `owner` is the name of the contract whose set up it is.
+    pub fn resolve(&self, owner: &str, contracts: &Vec<ContractInfo>) -> anyhow::Result<String> {
+        let name = match self {
+            SetUpTarget::This => owner,
+            SetUpTarget::Contract(name) => name,
+            SetUpTarget::Addr(addr) => {
+                let mut value = Value::String(addr.clone());
+                replace_strings(&mut value, contracts)?;
+                return match value {
+                    Value::String(addr) if !addr.starts_with('&') => Ok(addr),
+                    _ => Err(DeployError::ContractNotFound.into()),
+                };
+            }
+        };
+        contracts
+            .iter()
+            .find(|x| x.name == name)
+            .ok_or(DeployError::ContractNotFound)?
+            .addr
+            .clone()
+            .ok_or(
+                DeployError::AddrNotFound {
+                    name: name.to_string(),
+                }
+                .into(),
+            )
+    }
+}
+
+/// A set up msg along with where it is executed and the funds sent with it.
+#[derive(Debug, Clone)]
+pub struct SetUpItem<T> {
+    pub target: SetUpTarget,
+    pub msg: T,
+    pub funds: Vec<Coin>,
+    /// Signer role of this msg, defaults to the role of the contract for the set up stage
+    pub role: Option<String>,
+}
+
+impl<T> From<SetUpItem<T>> for SetUpItem<Box<dyn Msg>>
+where
+    T: Msg + Clone + 'static,
+{
+    fn from(item: SetUpItem<T>) -> Self {
+        SetUpItem {
+            target: item.target,
+            msg: Box::new(item.msg),
+            funds: item.funds,
+            role: item.role,
+        }
+    }
+}
+
+impl<T> From<SetUpMsg<T>> for SetUpItem<T> {
+    fn from(msg: SetUpMsg<T>) -> Self {
+        SetUpItem {
+            target: SetUpTarget::This,
+            msg: msg.msg,
+            funds: msg.funds,
+            role: None,
+        }
+    }
+}
+
+/// The response a verify query should return.
+/// Strings such as `&my_contract` are replaced with the address of the contract.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expected {
+    /// The response must equal this value
+    Exact(Value),
+    /// The response must contain the fields of this value, and may have others
+    Subset(Value),
+}
+
+#[derive(Debug, Clone)]
+pub struct VerifyQuery<T> {
+    pub msg: T,
+    pub expected: Expected,
+}
+
+impl<T> From<VerifyQuery<T>> for VerifyQuery<Box<dyn Msg>>
+where
+    T: Msg + Clone + 'static,
+{
+    fn from(query: VerifyQuery<T>) -> Self {
+        VerifyQuery {
+            msg: Box::new(query.msg),
+            expected: query.expected,
         }
     }
 }
@@ -1,11 +1,15 @@
 use std::str::FromStr;
 
-use crate::{contract::Deploy, file::CONFIG};
+use crate::{
+    contract::Deploy,
+    file::CONFIG,
+    history::record_tx,
+    tx::{broadcast, tx_config},
+};
 use colored::Colorize;
-use cosm_utils::clients::tendermint_rpc::ClientCompat;
-use cosm_utils::prelude::*;
+use cosm_utils::clients::{client::GetEvents, tendermint_rpc::ClientCompat};
 use cosm_utils::{
-    chain::{coin::Coin, request::TxOptions},
+    chain::coin::Coin,
     modules::{
         auth::model::Address,
         cosmwasm::model::{ExecRequest, InstantiateRequest},
@@ -37,15 +41,35 @@ pub async fn cw20_send(contract: &impl Deploy) -> anyhow::Result<()> {
     let chain_info = config.get_active_chain_info()?.clone();
     let client = HttpClient::get_persistent_compat(chain_info.rpc_endpoint.as_str()).await?;
     let funds = Vec::<Coin>::parse_to_obj()?;
+    let value = serde_json::to_value(&msg)?;
     let req = ExecRequest {
         msg,
         funds,
         address: Address::from_str(&cw20_contract_addr)?,
     };
 
-    let response = client
-        .wasm_execute_commit(&chain_info.cfg, req, &key, &TxOptions::default())
-        .await?;
+    let tx_config = tx_config(&config).await?;
+    let sender_addr = tx_config.sender_addr(&key, &chain_info.cfg).await?;
+    let signer_addr = tx_config.signer_addr(&key, &chain_info.cfg).await?;
+    let Some(response) = broadcast(
+        &client,
+        &chain_info.cfg,
+        vec![req.to_proto(sender_addr)?],
+        &key,
+        &tx_config,
+    )
+    .await?
+    else {
+        return Ok(());
+    };
+    record_tx(
+        &config,
+        "cw20_send",
+        &signer_addr,
+        &[(cw20_contract_addr, value)],
+        &response,
+    )
+    .await?;
     println!(
         "gas wanted: {}, gas used: {}",
         response.deliver_tx.gas_wanted.to_string().green(),
@@ -67,14 +91,34 @@ pub async fn cw20_execute() -> anyhow::Result<()> {
     let msg = Cw20ExecuteMsg::parse_to_obj()?;
     let chain_info = config.get_active_chain_info()?.clone();
     let client = HttpClient::get_persistent_compat(chain_info.rpc_endpoint.as_str()).await?;
+    let value = serde_json::to_value(&msg)?;
     let req = ExecRequest {
         msg,
         funds: vec![],
         address: Address::from_str(&cw20_contract_addr)?,
     };
-    let response = client
-        .wasm_execute_commit(&chain_info.cfg, req, &key, &TxOptions::default())
-        .await?;
+    let tx_config = tx_config(&config).await?;
+    let sender_addr = tx_config.sender_addr(&key, &chain_info.cfg).await?;
+    let signer_addr = tx_config.signer_addr(&key, &chain_info.cfg).await?;
+    let Some(response) = broadcast(
+        &client,
+        &chain_info.cfg,
+        vec![req.to_proto(sender_addr)?],
+        &key,
+        &tx_config,
+    )
+    .await?
+    else {
+        return Ok(());
+    };
+    record_tx(
+        &config,
+        "cw20_execute",
+        &signer_addr,
+        &[(cw20_contract_addr, value)],
+        &response,
+    )
+    .await?;
 
     println!(
         "gas wanted: {}, gas used: {}",
@@ -106,6 +150,7 @@ pub async fn cw20_instantiate() -> anyhow::Result<()> {
     let msg = cw20_base::msg::InstantiateMsg::parse_to_obj()?;
     let chain_info = config.get_active_chain_info()?.clone();
     let client = HttpClient::get_persistent_compat(chain_info.rpc_endpoint.as_str()).await?;
+    let value = serde_json::to_value(&msg)?;
     let req = InstantiateRequest {
         code_id,
         funds: vec![],
@@ -114,16 +159,40 @@ pub async fn cw20_instantiate() -> anyhow::Result<()> {
         admin,
     };
 
-    let response = client
-        .wasm_instantiate_commit(&chain_info.cfg, req, &key, &TxOptions::default())
-        .await?;
+    let tx_config = tx_config(&config).await?;
+    let sender_addr = tx_config.sender_addr(&key, &chain_info.cfg).await?;
+    let signer_addr = tx_config.signer_addr(&key, &chain_info.cfg).await?;
+    let Some(response) = broadcast(
+        &client,
+        &chain_info.cfg,
+        vec![req.to_proto(sender_addr)?],
+        &key,
+        &tx_config,
+    )
+    .await?
+    else {
+        return Ok(());
+    };
+    let cw20_contract_addr = response
+        .find_event_tags("instantiate".to_string(), "_contract_address".to_string())
+        .first()
+        .map(|x| x.value.to_string())
+        .unwrap_or("cw20".into());
+    record_tx(
+        &config,
+        "cw20_instantiate",
+        &signer_addr,
+        &[(cw20_contract_addr, value)],
+        &response,
+    )
+    .await?;
 
     println!(
         "gas wanted: {}, gas used: {}",
-        response.res.deliver_tx.gas_wanted.to_string().green(),
-        response.res.deliver_tx.gas_used.to_string().green()
+        response.deliver_tx.gas_wanted.to_string().green(),
+        response.deliver_tx.gas_used.to_string().green()
     );
-    println!("tx hash: {}", response.res.hash.to_string().purple());
+    println!("tx hash: {}", response.hash.to_string().purple());
 
     Ok(())
 }
@@ -1,23 +1,56 @@
-use std::str::FromStr;
+use std::{collections::BTreeMap, str::FromStr};
 
+use async_recursion::async_recursion;
+use colored::Colorize;
+use colored_json::to_colored_json_auto;
 use cosm_utils::{
-    chain::request::TxOptions,
+    chain::{msg::Msg as ProtoMsg, Any},
+    clients::client::GetEvents,
     modules::{
         auth::model::Address,
         cosmwasm::model::{ExecRequest, InstantiateRequest, MigrateRequest, StoreCodeRequest},
     },
     prelude::*,
+    signing_key::key::SigningKey,
 };
+use cosmrs::proto::traits::MessageExt;
+use serde::Serialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use strum_macros::Display;
 use tendermint_rpc::{endpoint::broadcast::tx_commit, HttpClient};
 
 use crate::{
-    contract::Deploy,
+    checksum::{code_provenance, wasm_checksum, CodeCache},
+    context::DeployContext,
+    contract::{Deploy, Msg},
     error::DeployError,
-    file::{ContractInfo, CONFIG},
+    file::{ChainInfo, CodeIdRecord, Config, ContractInfo, CHAIN_OVERRIDE, CONFIG, KEY_OVERRIDE},
+    history::record_tx,
+    instantiate2::{predict_address, Instantiate2Request},
+    journal::{Journal, JournalEntry},
+    proposal::{proposal_json, MsgSubmitProposal, ProposalConfig},
+    query::query_contract_info,
     settings::WorkspaceSettings,
-    utils::print_res,
+    tx::{broadcast, broadcast_batched, generate, TxConfig},
+    tx_file::EncodedMsg,
+    utils::{contract_label, print_res},
 };
 
+/// Code ids that a dry run gives to the code it would store, counting down from `u64::MAX`
+/// so that they are never mistaken for code on chain.
+pub const DRY_RUN_CODE_ID: u64 = u64::MAX;
+
+/// Address that a dry run gives to a contract it would instantiate.
+pub fn dry_run_addr(prefix: &str, name: &str) -> anyhow::Result<Address> {
+    Ok(Address::new(
+        prefix,
+        &Sha256::digest(format!("wasm-deploy dry run {name}")),
+    )?)
+}
+
+#[derive(Clone, Copy, Display)]
+#[strum(serialize_all = "snake_case")]
 pub enum DeploymentStage {
     StoreCode,
     Instantiate { interactive: bool },
@@ -27,199 +60,701 @@ pub enum DeploymentStage {
     SetUp,
 }
 
+impl DeploymentStage {
+    /// The signer role of the stage, unless a contract picks another one.
+    pub fn default_role(&self) -> &'static str {
+        match self {
+            DeploymentStage::StoreCode
+            | DeploymentStage::Instantiate { .. }
+            | DeploymentStage::ExternalInstantiate => "deployer",
+            DeploymentStage::Migrate { .. } => "admin",
+            DeploymentStage::SetConfig | DeploymentStage::SetUp => "operator",
+        }
+    }
+}
+
+/// A tx broadcast by a deployment stage,
+/// with the msgs it contained paired with the contract each belongs to.
+struct SentTx {
+    msgs: Vec<(String, Value)>,
+    response: tx_commit::Response,
+}
+
+/// Set up msgs that are signed by the same key.
+struct SignerBatch {
+    key: SigningKey,
+    /// Contract names and json of `reqs`, in the same order
+    msgs: Vec<(String, Value)>,
+    reqs: Vec<ExecRequest<Box<dyn Msg>>>,
+}
+
+/// A message that has been built for a deployment stage,
+/// paired with the contract it belongs to.
+struct SimulatedMsg {
+    contract: String,
+    json: Value,
+    any: Any,
+}
+
+/// Runs a deployment stage for `contracts`.
+/// Each stage that lands on chain is recorded in `journal` when one is given,
+/// which only `deploy` does so that it can be resumed.
+#[async_recursion(?Send)]
 pub async fn execute_deployment(
     settings: &WorkspaceSettings,
-    contracts: &[impl Deploy],
+    contracts: &[impl Deploy + Clone],
     // TODO: perhaps accept &[DeploymentStage]
     deployment_stage: DeploymentStage,
+    dry_run: bool,
+    mut journal: Option<&mut Journal>,
 ) -> anyhow::Result<()> {
-    let config = CONFIG.read().await;
-    let chain_info = config.get_active_chain_info()?.clone();
-    let key = config.get_active_key().await?;
-    let rpc_endpoint = chain_info.rpc_endpoint.clone();
-    drop(config);
-    let client = HttpClient::get_persistent_compat(rpc_endpoint.as_str()).await?;
-
-    let response: Option<tx_commit::Response> = match deployment_stage {
+    // Contracts pinned to another chain are deployed with that chain as the active one.
+    // Instantiations are split into dependency layers first,
+    // so that contracts referenced from another chain already exist.
+    if CHAIN_OVERRIDE.try_with(|_| ()).is_err() {
+        let config = CONFIG.read().await;
+        let env_chain_id = config.get_active_env()?.chain_id.clone();
+        let layers = match deployment_stage {
+            DeploymentStage::Instantiate { .. } => dependency_layers(contracts)?,
+            _ => vec![contracts.to_vec()],
+        };
+        let mut groups = vec![];
+        for layer in layers {
+            groups.extend(group_by_chain(&config, &layer)?);
+        }
+        drop(config);
+        if groups.iter().any(|(chain_id, _)| *chain_id != env_chain_id) {
+            for (chain_id, group) in groups {
+                CHAIN_OVERRIDE
+                    .scope(
+                        chain_id,
+                        execute_deployment(
+                            settings,
+                            &group,
+                            deployment_stage,
+                            dry_run,
+                            journal.as_deref_mut(),
+                        ),
+                    )
+                    .await?;
+            }
+            return Ok(());
+        }
+    }
+
+    let key_override = KEY_OVERRIDE.read().await.clone();
+    let env = CONFIG.read().await.get_active_env()?.clone();
+    let key_name = |role: &str| match &key_override {
+        Some(key_name) => key_name.clone(),
+        None => env.role_key_name(Some(role)).to_string(),
+    };
+
+    // Salted contracts get their addresses up front, so that contracts in any layer
+    // can reference them. The predictions are undone if the stage fails.
+    let mut predicted = vec![];
+    let mut result = Ok(());
+    if let DeploymentStage::Instantiate { .. } = deployment_stage {
+        for (role, group) in group_by_signer(contracts, &deployment_stage, key_name) {
+            result = async {
+                let ctx = DeployContext::load(dry_run, Some(&role)).await?;
+                predicted.extend(predict_salted_addresses(&ctx, &group).await?);
+                anyhow::Ok(())
+            }
+            .await;
+            if result.is_err() {
+                break;
+            }
+        }
+    }
+    if result.is_ok() {
+        for (role, group) in deployment_batches(contracts, &deployment_stage, key_name)? {
+            result = deploy_signer_group(
+                settings,
+                &group,
+                deployment_stage,
+                dry_run,
+                &role,
+                journal.as_deref_mut(),
+            )
+            .await;
+            if result.is_err() {
+                break;
+            }
+        }
+    }
+    if result.is_err() && !predicted.is_empty() {
+        let ctx = DeployContext::load(dry_run, None).await?;
+        forget_predictions(settings, &ctx.client, &predicted).await?;
+    }
+    result
+}
+
+/// Runs a deployment stage for contracts that are all signed for by the key of `role`.
+async fn deploy_signer_group(
+    settings: &WorkspaceSettings,
+    contracts: &[impl Deploy + Clone],
+    deployment_stage: DeploymentStage,
+    dry_run: bool,
+    role: &str,
+    journal: Option<&mut Journal>,
+) -> anyhow::Result<()> {
+    let mut ctx = DeployContext::load(dry_run, Some(role)).await?;
+    for contract in contracts {
+        contract.before_stage(&ctx, &deployment_stage).await?;
+    }
+    let result = run_stage(settings, contracts, deployment_stage, &ctx, journal).await;
+    // The hooks see the contract table as the stage left it
+    ctx.refresh().await?;
+    for contract in contracts {
+        contract
+            .after_stage(&ctx, &deployment_stage, &result)
+            .await?;
+    }
+    result
+}
+
+/// Builds and sends the msgs of a deployment stage and records the outcome.
+async fn run_stage(
+    settings: &WorkspaceSettings,
+    contracts: &[impl Deploy + Clone],
+    deployment_stage: DeploymentStage,
+    ctx: &DeployContext,
+    journal: Option<&mut Journal>,
+) -> anyhow::Result<()> {
+    let DeployContext {
+        chain_info,
+        client,
+        key,
+        sender_addr,
+        tx_config,
+        dry_run,
+        ..
+    } = ctx;
+    // Generated txs are only written to a file, so nothing is recorded as sent
+    let dry_run = *dry_run || tx_config.generate_only;
+    let stage = deployment_stage.to_string();
+    // The error of a tx that failed after earlier txs of the stage were committed
+    let mut failure = None;
+
+    let txs: Vec<SentTx> = match deployment_stage {
         DeploymentStage::StoreCode => {
+            let mut cache = CodeCache::load(settings, &chain_info.cfg.chain_id)?;
             let mut reqs = vec![];
+            let mut msgs = vec![];
+            let mut checksums = vec![];
+            // Checksums of the code that will be uploaded in this batch, in upload order
+            let mut uploads: Vec<String> = vec![];
+            let mut provenances = BTreeMap::new();
+            let config = CONFIG.read().await;
             for contract in contracts {
-                println!("Storing code for {}", contract.name());
-                let path = settings
-                    .artifacts_dir
-                    .join(format!("{}.wasm.gz", contract.bin_name()));
-                let wasm_data = std::fs::read(path)?;
-                reqs.push(StoreCodeRequest {
-                    wasm_data,
-                    instantiate_perms: None,
-                });
+                let checksum = wasm_checksum(
+                    &settings
+                        .artifacts_dir
+                        .join(format!("{}.wasm", contract.bin_name())),
+                )?;
+                let known_code_id = config
+                    .get_contract(&contract.to_string())
+                    .ok()
+                    .and_then(|x| x.code_id);
+                if let Some(code_id) = cache.find(client, &checksum, known_code_id).await? {
+                    println!(
+                        "Code for {} is already stored with code id {}",
+                        contract.name(),
+                        code_id
+                    );
+                    cache.codes.insert(checksum.clone(), code_id);
+                } else if !uploads.contains(&checksum) {
+                    println!("Storing code for {}", contract.name());
+                    let path = settings
+                        .artifacts_dir
+                        .join(format!("{}.wasm.gz", contract.bin_name()));
+                    let wasm_data = std::fs::read(&path)?;
+                    msgs.push((
+                        contract.name(),
+                        json!({ "wasm_file": path, "size": wasm_data.len() }),
+                    ));
+                    reqs.push(StoreCodeRequest {
+                        wasm_data,
+                        instantiate_perms: contract
+                            .instantiate_permission()
+                            .map(|x| x.to_access_config(sender_addr))
+                            .transpose()?,
+                    });
+                    provenances.insert(
+                        checksum.clone(),
+                        code_provenance(
+                            settings,
+                            &contract.bin_name(),
+                            &checksum,
+                            sender_addr.as_ref(),
+                        )?,
+                    );
+                    uploads.push(checksum.clone());
+                }
+                checksums.push(checksum);
             }
+            drop(config);
 
-            let response = client
-                .wasm_store_batch_commit(&chain_info.cfg, reqs, &key, &TxOptions::default())
-                .await?;
+            if dry_run || ctx.proposal.is_some() {
+                // Later stages of a dry run see the code ids the code would be stored with
+                if ctx.dry_run {
+                    let mut config = CONFIG.write().await;
+                    config.dry_run = true;
+                    for (contract, checksum) in contracts.iter().zip(&checksums) {
+                        let code_id = match uploads.iter().position(|x| x == checksum) {
+                            Some(i) => DRY_RUN_CODE_ID - i as u64,
+                            None => cache.codes[checksum],
+                        };
+                        match config.get_contract_mut(&contract.to_string()) {
+                            Ok(contract_info) => contract_info.code_id = Some(code_id),
+                            Err(_) => {
+                                config.add_contract_from(ContractInfo {
+                                    name: contract.name(),
+                                    addr: None,
+                                    code_id: Some(code_id),
+                                    chain_id: None,
+                                    provenance: None,
+                                    code_id_history: vec![],
+                                })?;
+                            }
+                        }
+                    }
+                }
+                if !reqs.is_empty() {
+                    let anys = reqs
+                        .into_iter()
+                        .map(|req| to_any(req.to_proto(sender_addr.clone())?))
+                        .collect::<anyhow::Result<Vec<_>>>()?;
+                    match &ctx.proposal {
+                        Some(proposal) => {
+                            let checksums = msgs
+                                .iter()
+                                .map(|(name, _)| name.clone())
+                                .zip(uploads)
+                                .collect::<Vec<_>>();
+                            propose(ctx, proposal, &stage, dry_run, msgs, anys, &checksums).await?
+                        }
+                        None => preview(client, chain_info, key, tx_config, msgs, anys).await?,
+                    }
+                }
+                return Ok(());
+            }
+
+            let protos = reqs
+                .into_iter()
+                .map(|req| Ok(req.to_proto(sender_addr.clone())?))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            let txs = send(
+                client,
+                chain_info,
+                key,
+                tx_config,
+                msgs,
+                protos,
+                &mut failure,
+            )
+            .await;
+            let mut code_ids = vec![];
+            for tx in &txs {
+                code_ids.extend(stored_code_ids(&tx.response)?);
+            }
+            for (checksum, code_id) in uploads.into_iter().zip(code_ids) {
+                if let Some(provenance) = provenances.get(&checksum) {
+                    cache
+                        .provenances
+                        .insert(checksum.clone(), provenance.clone());
+                }
+                cache.codes.insert(checksum, code_id);
+            }
+            cache.save(settings)?;
 
             let mut config = CONFIG.write().await;
-            for (i, contract) in contracts.iter().enumerate() {
+            for (contract, checksum) in contracts.iter().zip(checksums) {
+                let code_id = cache.codes.get(&checksum).copied();
+                // Code that was not stored before a failed tx is left as it was
+                if code_id.is_none() {
+                    continue;
+                }
+                // Code that was already on chain has the provenance it was cached with
+                let provenance = provenances
+                    .get(&checksum)
+                    .or(cache.provenances.get(&checksum))
+                    .cloned();
                 match config.get_contract_mut(&contract.to_string()) {
-                    Ok(contract_info) => contract_info.code_id = Some(response.code_ids[i]),
+                    Ok(contract_info) => {
+                        // Code that was already on chain keeps its provenance
+                        // unless it now points to different code
+                        if provenance.is_some() || contract_info.code_id != code_id {
+                            contract_info.provenance = provenance;
+                        }
+                        contract_info.code_id = code_id;
+                    }
                     Err(_) => {
                         config.add_contract_from(ContractInfo {
                             name: contract.name(),
                             addr: None,
-                            code_id: Some(response.code_ids[i]),
+                            code_id,
+                            chain_id: None,
+                            provenance,
+                            code_id_history: vec![],
                         })?;
                     }
                 }
             }
             config.save(settings)?;
-            Some(response.res)
+            txs
         }
         DeploymentStage::Instantiate { interactive } => {
             let mut reqs = vec![];
+            let mut msgs = vec![];
+            // Contracts with a salt are instantiated separately through MsgInstantiateContract2
+            let mut salted_reqs = vec![];
+            let mut salted_msgs = vec![];
+            let mut expected = vec![];
+            let mut salted_expected = vec![];
             let config = CONFIG.read().await;
             for contract in contracts {
                 let msg = if interactive {
                     Some(contract.instantiate()?)
                 } else {
-                    contract.instantiate_msg()
+                    contract.instantiate_msg(ctx)
                 };
-                if let Some(msg) = msg {
-                    println!("Instantiating {}", contract.name());
-                    let contract_info = config.get_contract(&contract.to_string())?;
-                    let code_id = contract_info.code_id.ok_or(DeployError::CodeIdNotFound)?;
-                    reqs.push(InstantiateRequest {
-                        code_id,
-                        msg,
-                        label: contract.name(),
-                        admin: Some(Address::from_str(&contract.admin())?),
-                        funds: vec![],
-                    });
-                } else if let Some(msg) = contract.instantiate_msg() {
+                if let Some(msg) = msg.or_else(|| contract.instantiate_msg(ctx)) {
                     println!("Instantiating {}", contract.name());
                     let contract_info = config.get_contract(&contract.to_string())?;
                     let code_id = contract_info.code_id.ok_or(DeployError::CodeIdNotFound)?;
-                    reqs.push(InstantiateRequest {
-                        code_id,
-                        msg,
-                        label: contract.name(),
-                        admin: Some(Address::from_str(&contract.admin())?),
-                        funds: vec![],
-                    });
+                    let env = config.get_active_env()?;
+                    let admin = env
+                        .admin(contract.admin())
+                        .as_deref()
+                        .map(Address::from_str)
+                        .transpose()?;
+                    let label =
+                        contract_label(settings, env, &contract.name(), Some(&contract.path()));
+                    match contract.salt() {
+                        Some(salt) => {
+                            salted_msgs.push((contract.name(), serde_json::to_value(&msg)?));
+                            salted_expected.push(ExpectedInstantiate {
+                                name: contract.to_string(),
+                                code_id,
+                                label: label.clone(),
+                            });
+                            salted_reqs.push(Instantiate2Request {
+                                code_id,
+                                msg,
+                                label,
+                                admin,
+                                funds: contract.instantiate_funds(),
+                                salt,
+                            });
+                        }
+                        None => {
+                            msgs.push((contract.name(), serde_json::to_value(&msg)?));
+                            expected.push(ExpectedInstantiate {
+                                name: contract.to_string(),
+                                code_id,
+                                label: label.clone(),
+                            });
+                            reqs.push(InstantiateRequest {
+                                code_id,
+                                msg,
+                                label,
+                                admin,
+                                funds: contract.instantiate_funds(),
+                            });
+                        }
+                    }
                 }
             }
-            let response = client
-                .wasm_instantiate_batch_commit(&chain_info.cfg, reqs, &key, &TxOptions::default())
-                .await?;
             drop(config);
+
+            if dry_run || ctx.proposal.is_some() {
+                let mut anys = reqs
+                    .into_iter()
+                    .map(|req| to_any(req.to_proto(sender_addr.clone())?))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                for req in salted_reqs {
+                    anys.push(to_any(req.to_proto(sender_addr.clone())?)?);
+                }
+                msgs.extend(salted_msgs);
+                match &ctx.proposal {
+                    Some(proposal) => {
+                        propose(ctx, proposal, &stage, dry_run, msgs, anys, &[]).await?
+                    }
+                    None => preview(client, chain_info, key, tx_config, msgs, anys).await?,
+                }
+                // Later stages of a dry run see the addresses the contracts would get.
+                // Salted contracts keep the address predicted for them.
+                if ctx.dry_run {
+                    let mut config = CONFIG.write().await;
+                    config.dry_run = true;
+                    let prefix = &chain_info.cfg.prefix;
+                    for expected in &expected {
+                        let addr = dry_run_addr(prefix, &expected.name)?;
+                        config.get_contract_mut(&expected.name)?.addr = Some(addr.to_string());
+                    }
+                    for expected in &salted_expected {
+                        let contract_info = config.get_contract_mut(&expected.name)?;
+                        if contract_info.addr.is_none() {
+                            let addr = dry_run_addr(prefix, &expected.name)?;
+                            contract_info.addr = Some(addr.to_string());
+                        }
+                    }
+                }
+                return Ok(());
+            }
+
+            // The addresses of salted contracts were already predicted
+            let salted_protos = salted_reqs
+                .into_iter()
+                .map(|req| Ok(req.to_proto(sender_addr.clone())?))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            let mut txs = send(
+                client,
+                chain_info,
+                key,
+                tx_config,
+                salted_msgs,
+                salted_protos,
+                &mut failure,
+            )
+            .await;
+            let protos = reqs
+                .into_iter()
+                .map(|req| Ok(req.to_proto(sender_addr.clone())?))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            txs.extend(
+                send(
+                    client,
+                    chain_info,
+                    key,
+                    tx_config,
+                    msgs,
+                    protos,
+                    &mut failure,
+                )
+                .await,
+            );
+            salted_expected.extend(expected);
+            let addresses = attribute_instantiations(client, &txs, &salted_expected).await?;
             let mut config = CONFIG.write().await;
-            for (index, contract) in contracts.iter().enumerate() {
-                let contract_info = config.get_contract_mut(&contract.to_string())?;
-                contract_info.addr = Some(response.addresses[index].to_string());
+            for (expected, addr) in salted_expected.iter().zip(addresses) {
+                let contract_info = config.get_contract_mut(&expected.name)?;
+                contract_info.addr = Some(addr);
             }
             config.save(settings)?;
-            Some(response.res)
+            txs
         }
         DeploymentStage::ExternalInstantiate => {
             let mut reqs = vec![];
+            let mut msgs = vec![];
+            let mut expected = vec![];
             let config = CONFIG.read().await;
+            let env = config.get_active_env()?;
             for contract in contracts {
-                for external in contract.external_instantiate_msgs() {
+                for external in contract.external_instantiate_msgs(ctx) {
                     println!("Instantiating {}", external.name);
+                    let label = contract_label(settings, env, &external.name, None);
+                    msgs.push((external.name.clone(), serde_json::to_value(&external.msg)?));
+                    expected.push(ExpectedInstantiate {
+                        name: external.name,
+                        code_id: external.code_id,
+                        label: label.clone(),
+                    });
                     reqs.push(InstantiateRequest {
                         code_id: external.code_id,
                         msg: external.msg,
-                        label: external.name.clone(),
-                        admin: Some(Address::from_str(&contract.admin())?),
-                        funds: vec![],
+                        label,
+                        admin: env
+                            .admin(contract.admin())
+                            .as_deref()
+                            .map(Address::from_str)
+                            .transpose()?,
+                        funds: external.funds,
                     });
                 }
             }
             drop(config);
             if reqs.is_empty() {
-                None
-            } else {
-                let response = client
-                    .wasm_instantiate_batch_commit(
-                        &chain_info.cfg,
-                        reqs,
-                        &key,
-                        &TxOptions::default(),
-                    )
-                    .await?;
-                let mut index = 0;
-                for contract in contracts {
-                    for external in contract.external_instantiate_msgs() {
-                        let mut config = CONFIG.write().await;
-                        config.add_contract_from(ContractInfo {
-                            name: external.name,
-                            addr: Some(response.addresses[index].to_string()),
-                            code_id: Some(external.code_id),
-                        })?;
-                        index += 1;
+                vec![]
+            } else if dry_run || ctx.proposal.is_some() {
+                let anys = reqs
+                    .into_iter()
+                    .map(|req| to_any(req.to_proto(sender_addr.clone())?))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                match &ctx.proposal {
+                    Some(proposal) => {
+                        propose(ctx, proposal, &stage, dry_run, msgs, anys, &[]).await?
                     }
+                    None => preview(client, chain_info, key, tx_config, msgs, anys).await?,
+                }
+                return Ok(());
+            } else {
+                let protos = reqs
+                    .into_iter()
+                    .map(|req| Ok(req.to_proto(sender_addr.clone())?))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                let txs = send(
+                    client,
+                    chain_info,
+                    key,
+                    tx_config,
+                    msgs,
+                    protos,
+                    &mut failure,
+                )
+                .await;
+                let addresses = attribute_instantiations(client, &txs, &expected).await?;
+                let mut config = CONFIG.write().await;
+                for (expected, addr) in expected.into_iter().zip(addresses) {
+                    let chain_id = config
+                        .get_contract(&expected.name)
+                        .ok()
+                        .and_then(|x| x.chain_id.clone());
+                    config.add_contract_from(ContractInfo {
+                        name: expected.name,
+                        addr: Some(addr),
+                        code_id: Some(expected.code_id),
+                        chain_id,
+                        provenance: None,
+                        code_id_history: vec![],
+                    })?;
                 }
-                let config = CONFIG.read().await;
                 config.save(settings)?;
-                Some(response.res)
+                txs
             }
         }
         DeploymentStage::SetConfig => {
             let mut reqs = vec![];
+            let mut msgs = vec![];
             let config = CONFIG.read().await;
             for contract in contracts {
-                if let Some(msg) = contract.set_config_msg() {
+                if let Some(msg) = contract.set_config_msg(ctx) {
                     println!("Setting config for {}", contract.name());
                     let contract_addr = config.get_contract_addr(&contract.to_string())?.clone();
+                    msgs.push((contract.name(), serde_json::to_value(&msg)?));
                     reqs.push(ExecRequest {
                         msg,
-                        funds: vec![],
+                        funds: contract.set_config_funds(),
                         address: Address::from_str(&contract_addr)?,
                     });
                 };
             }
             if reqs.is_empty() {
-                None
+                vec![]
+            } else if dry_run {
+                let anys = reqs
+                    .into_iter()
+                    .map(|req| to_any(req.to_proto(sender_addr.clone())?))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                preview(client, chain_info, key, tx_config, msgs, anys).await?;
+                vec![]
             } else {
-                let response = client
-                    .wasm_execute_batch_commit(&chain_info.cfg, reqs, &key, &TxOptions::default())
-                    .await?;
-                Some(response)
+                let protos = reqs
+                    .into_iter()
+                    .map(|req| Ok(req.to_proto(sender_addr.clone())?))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                send(
+                    client,
+                    chain_info,
+                    key,
+                    tx_config,
+                    msgs,
+                    protos,
+                    &mut failure,
+                )
+                .await
             }
         }
         DeploymentStage::SetUp => {
-            let mut reqs = vec![];
+            // Msgs with a signer role of their own are sent in separate txs, one per key
+            let mut signers = vec![SignerBatch {
+                key: key.clone(),
+                msgs: vec![],
+                reqs: vec![],
+            }];
             let config = CONFIG.read().await;
+            let env_contracts = &config.get_active_env()?.contracts;
             for contract in contracts {
-                for (i, msg) in contract.set_up_msgs().into_iter().enumerate() {
+                for (i, set_up) in contract.set_up_items(ctx).into_iter().enumerate() {
                     if i == 0 {
                         println!("Executing Set Up for {}", contract.name());
                     }
-                    let contract_addr = config.get_contract_addr(&contract.to_string())?.clone();
-                    reqs.push(ExecRequest {
-                        msg,
-                        funds: vec![],
-                        address: Address::from_str(&contract_addr)?,
+                    // Targets are resolved now so that they see the addresses of this deployment
+                    let target_addr = set_up
+                        .target
+                        .resolve(&contract.to_string(), env_contracts)?;
+                    let key_name = match &set_up.role {
+                        Some(role) => config.role_key_name(Some(role)).await?,
+                        None => key.name.clone(),
+                    };
+                    let index = match signers.iter().position(|x| x.key.name == key_name) {
+                        Some(index) => index,
+                        None => {
+                            signers.push(SignerBatch {
+                                key: config.get_key(&key_name).await?,
+                                msgs: vec![],
+                                reqs: vec![],
+                            });
+                            signers.len() - 1
+                        }
+                    };
+                    let batch = &mut signers[index];
+                    batch
+                        .msgs
+                        .push((contract.name(), serde_json::to_value(&set_up.msg)?));
+                    batch.reqs.push(ExecRequest {
+                        msg: set_up.msg,
+                        funds: set_up.funds,
+                        address: Address::from_str(&target_addr)?,
                     });
                 }
             }
-            if reqs.is_empty() {
-                None
-            } else {
-                let response = client
-                    .wasm_execute_batch_commit(&chain_info.cfg, reqs, &key, &TxOptions::default())
-                    .await?;
-                Some(response)
+            drop(config);
+            let mut txs = vec![];
+            for SignerBatch { key, msgs, reqs } in signers {
+                if reqs.is_empty() {
+                    continue;
+                }
+                let sender_addr = tx_config.sender_addr(&key, &chain_info.cfg).await?;
+                if dry_run {
+                    let anys = reqs
+                        .into_iter()
+                        .map(|req| to_any(req.to_proto(sender_addr.clone())?))
+                        .collect::<anyhow::Result<Vec<_>>>()?;
+                    preview(client, chain_info, &key, tx_config, msgs, anys).await?;
+                } else {
+                    let protos = reqs
+                        .into_iter()
+                        .map(|req| Ok(req.to_proto(sender_addr.clone())?))
+                        .collect::<anyhow::Result<Vec<_>>>()?;
+                    txs.extend(
+                        send(
+                            client,
+                            chain_info,
+                            &key,
+                            tx_config,
+                            msgs,
+                            protos,
+                            &mut failure,
+                        )
+                        .await,
+                    );
+                }
             }
+            txs
         }
         DeploymentStage::Migrate { interactive } => {
             let mut reqs = vec![];
+            let mut msgs = vec![];
+            // The contract names and the code ids they are migrating from
+            let mut migrated = vec![];
             let config = CONFIG.read().await;
             for contract in contracts {
                 let msg = if interactive {
                     Some(contract.migrate()?)
                 } else {
-                    contract.migrate_msg()
+                    contract.migrate_msg(ctx)
                 };
                 if let Some(msg) = msg {
                     println!("Migrating {}", contract.name());
@@ -232,6 +767,16 @@ pub async fn execute_deployment(
                                 name: contract_info.name.clone(),
                             })?;
                     let code_id = contract_info.code_id.ok_or(DeployError::CodeIdNotFound)?;
+                    msgs.push((contract.name(), serde_json::to_value(&msg)?));
+                    if !dry_run {
+                        let previous_code_id = query_contract_info(client, &contract_addr)
+                            .await?
+                            .ok_or(DeployError::AddrNotFound {
+                                name: contract.name(),
+                            })?
+                            .code_id;
+                        migrated.push((contract.to_string(), previous_code_id));
+                    }
                     reqs.push(MigrateRequest {
                         msg,
                         address: Address::from_str(&contract_addr)?,
@@ -239,15 +784,599 @@ pub async fn execute_deployment(
                     });
                 }
             }
-            let response = client
-                .wasm_migrate_batch_commit(&chain_info.cfg, reqs, &key, &TxOptions::default())
-                .await?;
-            Some(response)
+            drop(config);
+            if dry_run || ctx.proposal.is_some() {
+                let anys = reqs
+                    .into_iter()
+                    .map(|req| to_any(req.to_proto(sender_addr.clone())?))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                match &ctx.proposal {
+                    Some(proposal) => {
+                        propose(ctx, proposal, &stage, dry_run, msgs, anys, &[]).await?
+                    }
+                    None => preview(client, chain_info, key, tx_config, msgs, anys).await?,
+                }
+                return Ok(());
+            }
+            let protos = reqs
+                .into_iter()
+                .map(|req| Ok(req.to_proto(sender_addr.clone())?))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            let txs = send(
+                client,
+                chain_info,
+                key,
+                tx_config,
+                msgs,
+                protos,
+                &mut failure,
+            )
+            .await;
+            let mut config = CONFIG.write().await;
+            let sent = txs
+                .iter()
+                .flat_map(|tx| tx.msgs.iter().map(|(_, msg)| (msg, tx.response.hash)));
+            for ((name, previous_code_id), (msg, tx_hash)) in migrated.into_iter().zip(sent) {
+                let contract_info = config.get_contract_mut(&name)?;
+                let code_id = contract_info.code_id.ok_or(DeployError::CodeIdNotFound)?;
+                contract_info.push_code_id(
+                    previous_code_id,
+                    CodeIdRecord {
+                        code_id,
+                        migrate_msg: Some(msg.clone()),
+                        tx_hash: Some(tx_hash.to_string()),
+                    },
+                );
+            }
+            config.save(settings)?;
+            txs
         }
     };
-    if let Some(res) = response {
-        print_res(res);
+
+    let signer_addr = tx_config.signer_addr(key, &chain_info.cfg).await?;
+    for tx in &txs {
+        record_tx(
+            &*CONFIG.read().await,
+            &stage,
+            &signer_addr,
+            &tx.msgs,
+            &tx.response,
+        )
+        .await?;
+    }
+
+    if let Some(journal) = journal.filter(|_| !dry_run) {
+        let config = CONFIG.read().await;
+        for contract in contracts {
+            let sent = txs
+                .iter()
+                .any(|tx| tx.msgs.iter().any(|(name, _)| *name == contract.name()));
+            // After a failure only the contracts whose msgs were committed are complete
+            if failure.is_some() && !sent {
+                continue;
+            }
+            journal.record(JournalEntry {
+                stage: stage.clone(),
+                contract: contract.name(),
+                tx_hash: txs
+                    .iter()
+                    .find(|tx| tx.msgs.iter().any(|(name, _)| *name == contract.name()))
+                    .map(|tx| tx.response.hash.to_string()),
+                result: config.get_contract(&contract.to_string()).ok().cloned(),
+            });
+        }
+        journal.save(settings)?;
+    }
+
+    for tx in txs {
+        print_res(tx.response);
+    }
+
+    match failure {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// Broadcasts the msgs of a deployment stage, split into as many txs as needed,
+/// and returns the txs that were committed.
+/// `msgs` are the contract names and json of `protos`, in the same order.
+/// If a tx fails its error is put in `failure`, and nothing more is sent while it is set,
+/// so that the stage can still record what landed on chain before returning the error.
+async fn send<T>(
+    client: &HttpClient,
+    chain_info: &ChainInfo,
+    key: &SigningKey,
+    tx_config: &TxConfig,
+    msgs: Vec<(String, Value)>,
+    protos: Vec<T>,
+    failure: &mut Option<anyhow::Error>,
+) -> Vec<SentTx>
+where
+    T: ProtoMsg + Serialize + Send + Sync,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    if protos.is_empty() || failure.is_some() {
+        return vec![];
+    }
+    let batches = match broadcast_batched(client, &chain_info.cfg, protos, key, tx_config).await {
+        Ok(batches) => batches,
+        Err((batches, e)) => {
+            *failure = Some(e);
+            batches
+        }
+    };
+    batches
+        .into_iter()
+        .map(|batch| SentTx {
+            msgs: msgs[batch.range].to_vec(),
+            response: batch.response,
+        })
+        .collect()
+}
+
+/// Sets the address of every salted contract to the address it will be instantiated at,
+/// so that instantiate msgs can reference contracts that have not been instantiated yet.
+/// In a dry run, code that would be stored first gets a placeholder address instead.
+/// Returns the names, previous addresses and predicted addresses of those contracts.
+async fn predict_salted_addresses(
+    ctx: &DeployContext,
+    contracts: &[impl Deploy],
+) -> anyhow::Result<Vec<(String, Option<String>, String)>> {
+    let mut predicted = vec![];
+    let mut config = CONFIG.write().await;
+    for contract in contracts {
+        if let Some(salt) = contract.salt() {
+            let contract_info = config.get_contract_mut(&contract.to_string())?;
+            if let Some(code_id) = contract_info.code_id {
+                let addr = match predict_address(&ctx.client, code_id, &ctx.sender_addr, &salt)
+                    .await
+                {
+                    Ok(addr) => addr,
+                    Err(e)
+                        if ctx.dry_run
+                            && matches!(e.downcast_ref(), Some(DeployError::CodeIdNotFound)) =>
+                    {
+                        dry_run_addr(&ctx.chain_info.cfg.prefix, &contract.to_string())?
+                    }
+                    Err(e) => return Err(e),
+                };
+                predicted.push((
+                    contract.to_string(),
+                    contract_info.addr.clone(),
+                    addr.to_string(),
+                ));
+                contract_info.addr = Some(addr.to_string());
+            }
+        }
+    }
+    Ok(predicted)
+}
+
+/// Puts back the previous address of every predicted contract that was not instantiated,
+/// so that a failed stage does not leave addresses of contracts that do not exist.
+async fn forget_predictions(
+    settings: &WorkspaceSettings,
+    client: &HttpClient,
+    predicted: &[(String, Option<String>, String)],
+) -> anyhow::Result<()> {
+    let mut config = CONFIG.write().await;
+    for (name, previous, addr) in predicted {
+        let contract_info = config.get_contract_mut(name)?;
+        if contract_info.addr.as_ref() == Some(addr)
+            && query_contract_info(client, addr).await?.is_none()
+        {
+            contract_info.addr = previous.clone();
+        }
+    }
+    config.save(settings)
+}
+
+/// Sorts the contracts into layers where every contract only depends on contracts in earlier layers.
+/// Dependencies that are not part of `contracts` are assumed to already be deployed.
+pub fn dependency_layers<C: Deploy + Clone>(contracts: &[C]) -> Result<Vec<Vec<C>>, DeployError> {
+    let mut remaining: Vec<C> = contracts.to_vec();
+    let mut layers: Vec<Vec<C>> = vec![];
+    while !remaining.is_empty() {
+        let (ready, blocked): (Vec<C>, Vec<C>) = remaining.iter().cloned().partition(|contract| {
+            contract
+                .depends_on()
+                .iter()
+                .all(|dep| !remaining.iter().any(|x| x.name() == dep.name()))
+        });
+        if ready.is_empty() {
+            return Err(DeployError::DependencyCycle {
+                contracts: blocked
+                    .iter()
+                    .map(|x| x.name())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            });
+        }
+        layers.push(ready);
+        remaining = blocked;
+    }
+    Ok(layers)
+}
+
+/// Groups contracts by the key that signs for them in the given stage, in order of first appearance.
+/// Each group is paired with one of the roles that resolve to its key.
+fn group_by_signer<C: Deploy + Clone>(
+    contracts: &[C],
+    stage: &DeploymentStage,
+    key_name: impl Fn(&str) -> String,
+) -> Vec<(String, Vec<C>)> {
+    let mut groups: Vec<(String, String, Vec<C>)> = vec![];
+    for contract in contracts {
+        let role = contract.signer_role(stage);
+        let key_name = key_name(&role);
+        match groups.iter_mut().find(|(x, _, _)| *x == key_name) {
+            Some((_, _, group)) => group.push(contract.clone()),
+            None => groups.push((key_name, role, vec![contract.clone()])),
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(_, role, group)| (role, group))
+        .collect()
+}
+
+/// Splits the contracts of a stage into the batches they are deployed in, in order,
+/// each paired with the signer role of its contracts.
+/// Instantiations are split into dependency layers first, so that dependencies signed for
+/// by another key still come first. Every layer is then split by the key that signs for
+/// its contracts, which `key_name` returns for a role.
+pub fn deployment_batches<C: Deploy + Clone>(
+    contracts: &[C],
+    stage: &DeploymentStage,
+    key_name: impl Fn(&str) -> String,
+) -> Result<Vec<(String, Vec<C>)>, DeployError> {
+    let layers = match stage {
+        DeploymentStage::Instantiate { .. } => dependency_layers(contracts)?,
+        _ => vec![contracts.to_vec()],
+    };
+    Ok(layers
+        .iter()
+        .flat_map(|layer| group_by_signer(layer, stage, &key_name))
+        .collect())
+}
+
+/// Groups contracts by the chain they are deployed on, in order of first appearance.
+fn group_by_chain<C: Deploy + Clone>(
+    config: &Config,
+    contracts: &[C],
+) -> anyhow::Result<Vec<(String, Vec<C>)>> {
+    let mut groups: Vec<(String, Vec<C>)> = vec![];
+    for contract in contracts {
+        let chain_id = config.contract_chain_id(&contract.to_string())?;
+        match groups.iter_mut().find(|(x, _)| *x == chain_id) {
+            Some((_, group)) => group.push(contract.clone()),
+            None => groups.push((chain_id, vec![contract.clone()])),
+        }
+    }
+    Ok(groups)
+}
+
+/// Code ids of the code stored by a tx, in msg order.
+fn stored_code_ids(res: &tx_commit::Response) -> anyhow::Result<Vec<u64>> {
+    Ok(res
+        .find_event_tags("store_code".to_string(), "code_id".to_string())
+        .into_iter()
+        .map(|x| x.value.parse::<u64>())
+        .collect::<Result<Vec<_>, _>>()?)
+}
+
+/// What an instantiate msg asked for, used to find the contract it created.
+struct ExpectedInstantiate {
+    /// Name of the contract in the config
+    name: String,
+    code_id: u64,
+    label: String,
+}
+
+/// A contract instantiated by a tx, as reported by its `instantiate` event.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstantiateEvent {
+    /// Only set by chains that tag events with the index of their msg
+    pub msg_index: Option<usize>,
+    pub address: String,
+    pub code_id: Option<u64>,
+}
+
+/// Reads the `instantiate` events of a tx, in the order they were emitted.
+pub fn instantiate_events(res: &tx_commit::Response) -> Vec<InstantiateEvent> {
+    res.deliver_tx
+        .events
+        .iter()
+        .filter(|event| event.kind == "instantiate")
+        .filter_map(|event| {
+            let attr = |key: &str| {
+                event
+                    .attributes
+                    .iter()
+                    .find(|x| x.key == key)
+                    .map(|x| x.value.clone())
+            };
+            Some(InstantiateEvent {
+                msg_index: attr("msg_index").and_then(|x| x.parse().ok()),
+                address: attr("_contract_address")?,
+                code_id: attr("code_id").and_then(|x| x.parse().ok()),
+            })
+        })
+        .collect()
+}
+
+/// Finds the event of each instantiate msg in a tx, in msg order.
+/// Events are matched by msg index when the chain provides it and otherwise in order,
+/// skipping contracts with a different code id such as those instantiated by a contract.
+pub fn match_instantiate_events(
+    events: &[InstantiateEvent],
+    code_ids: &[u64],
+) -> Vec<Option<InstantiateEvent>> {
+    let indexed = events.iter().any(|x| x.msg_index.is_some());
+    let mut cursor = 0;
+    code_ids
+        .iter()
+        .enumerate()
+        .map(|(i, code_id)| {
+            let matches = |event: &InstantiateEvent| {
+                event.code_id.is_none() || event.code_id == Some(*code_id)
+            };
+            if indexed {
+                events
+                    .iter()
+                    .find(|x| x.msg_index == Some(i) && matches(x))
+                    .cloned()
+            } else {
+                let position = events[cursor..].iter().position(matches)?;
+                cursor += position + 1;
+                Some(events[cursor - 1].clone())
+            }
+        })
+        .collect()
+}
+
+/// Returns the address of each expected contract, in order,
+/// after checking it against the instantiate events and the label on chain.
+async fn attribute_instantiations(
+    client: &HttpClient,
+    txs: &[SentTx],
+    expected: &[ExpectedInstantiate],
+) -> anyhow::Result<Vec<String>> {
+    let mut addresses = vec![];
+    let mut remaining = expected;
+    for tx in txs {
+        let (in_tx, rest) = remaining.split_at(tx.msgs.len().min(remaining.len()));
+        remaining = rest;
+        let code_ids = in_tx.iter().map(|x| x.code_id).collect::<Vec<_>>();
+        let events = instantiate_events(&tx.response);
+        for (expected, event) in in_tx
+            .iter()
+            .zip(match_instantiate_events(&events, &code_ids))
+        {
+            let mismatch = || DeployError::InstantiateMismatch {
+                name: expected.name.clone(),
+                tx_hash: tx.response.hash.to_string(),
+            };
+            let event = event.ok_or_else(mismatch)?;
+            let label = query_contract_info(client, &event.address)
+                .await?
+                .map(|x| x.label);
+            if label.as_ref() != Some(&expected.label) {
+                return Err(mismatch().into());
+            }
+            addresses.push(event.address);
+        }
+    }
+    Ok(addresses)
+}
+
+/// Simulates the msgs of a deployment stage,
+/// or writes them to an unsigned tx file when the tx config is `generate_only`.
+async fn preview(
+    client: &HttpClient,
+    chain_info: &ChainInfo,
+    key: &SigningKey,
+    tx_config: &TxConfig,
+    msgs: Vec<(String, Value)>,
+    anys: Vec<Any>,
+) -> anyhow::Result<()> {
+    let signer_addr = tx_config.signer_addr(key, &chain_info.cfg).await?;
+    let encoded = anys.into_iter().map(EncodedMsg).collect::<Vec<_>>();
+    let wrapped = tx_config.authz_wrap(&encoded, &signer_addr)?;
+    if tx_config.generate_only {
+        for (contract, json) in &msgs {
+            println!("{}", contract.bold());
+            println!("{}", to_colored_json_auto(json)?);
+        }
+        generate(client, &chain_info.cfg, &signer_addr, &wrapped, tx_config).await?;
+        Ok(())
+    } else {
+        let anys = wrapped.into_iter().map(|x| x.0).collect();
+        simulate(client, chain_info, &signer_addr, msgs, anys).await
+    }
+}
+
+/// Submits the msgs of a deployment stage in a governance proposal,
+/// or writes the proposal to the `proposal_file` of the proposal config.
+/// `checksums` are the names and checksums of the contracts whose code is stored,
+/// and are added to the summary so that voters can check the code.
+async fn propose(
+    ctx: &DeployContext,
+    proposal: &ProposalConfig,
+    stage: &str,
+    dry_run: bool,
+    msgs: Vec<(String, Value)>,
+    anys: Vec<Any>,
+    checksums: &[(String, String)],
+) -> anyhow::Result<()> {
+    if anys.is_empty() {
+        return Ok(());
+    }
+    let DeployContext {
+        chain_info,
+        client,
+        key,
+        tx_config,
+        ..
+    } = ctx;
+    let names = unique(msgs.iter().map(|(name, _)| name.as_str()));
+    let title = proposal
+        .title
+        .clone()
+        .unwrap_or_else(|| format!("{stage} {}", names.join(", ")));
+    let mut summary = proposal.summary.clone().unwrap_or(title.clone());
+    if !checksums.is_empty() {
+        summary.push_str("\n\nCode checksums:");
+        for (name, checksum) in checksums {
+            summary.push_str(&format!("\n{name}: {checksum}"));
+        }
+    }
+    // The proposer is the account that would otherwise have sent the msgs
+    let msg = MsgSubmitProposal {
+        messages: anys,
+        initial_deposit: proposal.initial_deposit()?,
+        proposer: tx_config
+            .sender_addr(key, &chain_info.cfg)
+            .await?
+            .to_string(),
+        metadata: String::new(),
+        title,
+        summary,
+    };
+    let json = proposal_json(&msg)?;
+
+    if let Some(path) = &proposal.proposal_file {
+        std::fs::write(path, serde_json::to_vec_pretty(&json)?)?;
+        println!("Proposal written to {}", path.display());
+        return Ok(());
+    }
+    let any = msg.to_any()?;
+    if dry_run {
+        let preview_msgs = vec![(names.join(", "), json)];
+        return preview(client, chain_info, key, tx_config, preview_msgs, vec![any]).await;
+    }
+    let Some(response) = broadcast(
+        client,
+        &chain_info.cfg,
+        vec![EncodedMsg(any)],
+        key,
+        tx_config,
+    )
+    .await?
+    else {
+        return Ok(());
+    };
+    for proposal_id in
+        response.find_event_tags("submit_proposal".to_string(), "proposal_id".to_string())
+    {
+        println!("Submitted proposal {}", proposal_id.value.bold());
+    }
+    record_tx(
+        &*CONFIG.read().await,
+        &format!("{stage}_proposal"),
+        &tx_config.signer_addr(key, &chain_info.cfg).await?,
+        &msgs,
+        &response,
+    )
+    .await?;
+    print_res(response);
+    Ok(())
+}
+
+fn to_any<M: ProtoMsg>(msg: M) -> anyhow::Result<Any>
+where
+    M::Err: std::error::Error + Send + Sync + 'static,
+{
+    Ok(msg.into_any()?)
+}
+
+/// Simulates the msgs of a deployment stage against the node instead of broadcasting them.
+/// Each contract is simulated on its own so that gas and fees can be reported per contract,
+/// then the whole batch is simulated as it would have been sent.
+pub async fn simulate(
+    client: &HttpClient,
+    chain_info: &ChainInfo,
+    sender_addr: &Address,
+    msgs: Vec<(String, Value)>,
+    anys: Vec<Any>,
+) -> anyhow::Result<()> {
+    let account = Auth::auth_query_account(client, sender_addr.clone())
+        .await?
+        .account;
+
+    let mut simulated: Vec<SimulatedMsg> = vec![];
+    for ((contract, json), any) in msgs.into_iter().zip(anys) {
+        simulated.push(SimulatedMsg {
+            contract,
+            json,
+            any,
+        });
+    }
+
+    let names = unique(simulated.iter().map(|x| &x.contract));
+    for name in names {
+        println!("Simulating {}", name.bold());
+        let contract_msgs = simulated.iter().filter(|x| &x.contract == name);
+        for msg in contract_msgs.clone() {
+            println!("{}", to_colored_json_auto(&msg.json)?);
+        }
+        let result = client
+            .tx_simulate(
+                &chain_info.cfg.denom,
+                chain_info.cfg.gas_price,
+                chain_info.cfg.gas_adjustment,
+                contract_msgs.map(|x| x.any.clone()).collect::<Vec<_>>(),
+                &account,
+            )
+            .await;
+        match result {
+            Ok(fee) => print_fee(&fee),
+            Err(e) => println!("{} {}", "simulation failed:".red(), e),
+        }
+    }
+
+    let result = client
+        .tx_simulate(
+            &chain_info.cfg.denom,
+            chain_info.cfg.gas_price,
+            chain_info.cfg.gas_adjustment,
+            simulated.into_iter().map(|x| x.any).collect::<Vec<_>>(),
+            &account,
+        )
+        .await;
+    println!("{}", "Total for batch".bold());
+    // Msgs that use the placeholder code ids or addresses of a dry run can't be simulated,
+    // which should not stop the rest of the dry run
+    match result {
+        Ok(fee) => print_fee(&fee),
+        Err(e) => println!("{} {}", "simulation failed:".red(), e),
     }
 
     Ok(())
 }
+
+/// The distinct items, in the order they are first seen.
+fn unique<T: PartialEq>(items: impl IntoIterator<Item = T>) -> Vec<T> {
+    let mut unique = vec![];
+    for item in items {
+        if !unique.contains(&item) {
+            unique.push(item);
+        }
+    }
+    unique
+}
+
+fn print_fee(fee: &cosm_utils::chain::fee::Fee) {
+    let amount = fee
+        .amount
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    println!(
+        "estimated gas: {}, fee: {}",
+        fee.gas_limit.value().to_string().green(),
+        amount.green()
+    );
+}
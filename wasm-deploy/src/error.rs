@@ -80,6 +80,58 @@ pub enum DeployError {
 
     #[error("Response received from client was empty")]
     EmptyResponse,
+
+    #[error("Dependency cycle detected between contracts: {contracts}")]
+    DependencyCycle { contracts: String },
+
+    #[error("Salt must be between 1 and 64 bytes")]
+    InvalidSalt,
+
+    #[error("No admin given and the active env has no admin override")]
+    AdminNotFound,
+
+    #[error("The active env has no authz granter, set one or pass --authz-granter")]
+    AuthzGranterNotFound,
+
+    #[error("No earlier code id recorded for {name}, use --to to pick one")]
+    RollbackTargetNotFound { name: String },
+
+    #[error("Invalid coin {coin}, expected an amount followed by a denom such as 5000uosmo")]
+    InvalidCoin { coin: String },
+
+    #[error("Could not match {name} to a contract instantiated by tx {tx_hash}")]
+    InstantiateMismatch { name: String, tx_hash: String },
+
+    #[error("{failed} of {total} verify queries failed")]
+    VerificationFailed { failed: usize, total: usize },
+
+    #[error("{failed} of {total} envs failed")]
+    EnvsFailed { failed: usize, total: usize },
+
+    #[error("Msg type {type_url} is not supported in tx files")]
+    UnsupportedMsgType { type_url: String },
+
+    #[error("Invalid tx file: {reason}")]
+    InvalidTxFile { reason: String },
+
+    #[error(
+        "The tx must be signed by {signers}, but the key signs as {addr}, pick its key with --key"
+    )]
+    SignerMismatch { signers: String, addr: String },
+
+    #[error("Tx {tx_hash} failed with code {code} in codespace {codespace}: {log}")]
+    TxFailed {
+        code: u32,
+        codespace: String,
+        log: String,
+        tx_hash: String,
+    },
+
+    #[error("Proposal {proposal_id} not found")]
+    ProposalNotFound { proposal_id: u64 },
+
+    #[error("Proposal {proposal_id} has not passed, its status is {status}")]
+    ProposalNotPassed { proposal_id: u64, status: String },
 }
 
 #[cfg(test)]
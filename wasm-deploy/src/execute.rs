@@ -1,10 +1,12 @@
 use crate::{
     contract::Deploy,
     file::{Config, CONFIG},
+    history::record_tx,
+    tx::{broadcast, tx_config},
 };
 use colored::Colorize;
 use cosm_utils::{
-    chain::{coin::Coin, request::TxOptions},
+    chain::coin::Coin,
     modules::{auth::model::Address, cosmwasm::model::ExecRequest},
     prelude::*,
 };
@@ -32,14 +34,34 @@ pub async fn execute(
     let key = config.get_active_key().await?;
     let chain_info = config.get_active_chain_info()?.clone();
     let client = HttpClient::get_persistent_compat(chain_info.rpc_endpoint.as_str()).await?;
+    let name = config
+        .get_active_env()?
+        .contracts
+        .iter()
+        .find(|x| x.addr.as_deref() == Some(addr.as_ref()))
+        .map(|x| x.name.clone())
+        .unwrap_or(addr.as_ref().to_string());
+    let value = serde_json::to_value(&msg)?;
     let req = ExecRequest {
         msg,
         funds,
         address: Address::from_str(addr.as_ref())?,
     };
-    let response = client
-        .wasm_execute_commit(&chain_info.cfg, req, &key, &TxOptions::default())
-        .await?;
+    let tx_config = tx_config(config).await?;
+    let sender_addr = tx_config.sender_addr(&key, &chain_info.cfg).await?;
+    let signer_addr = tx_config.signer_addr(&key, &chain_info.cfg).await?;
+    let Some(response) = broadcast(
+        &client,
+        &chain_info.cfg,
+        vec![req.to_proto(sender_addr)?],
+        &key,
+        &tx_config,
+    )
+    .await?
+    else {
+        return Ok(());
+    };
+    record_tx(config, "execute", &signer_addr, &[(name, value)], &response).await?;
     println!(
         "gas wanted: {}, gas used: {}",
         response.deliver_tx.gas_wanted.to_string().green(),
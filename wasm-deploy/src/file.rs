@@ -1,6 +1,8 @@
 #[cfg(feature = "ledger")]
 use crate::ledger::get_ledger_info;
-use crate::{error::DeployError, settings::WorkspaceSettings};
+use crate::{
+    error::DeployError, proposal::ProposalConfig, settings::WorkspaceSettings, tx::TxConfig,
+};
 use cosm_utils::prelude::*;
 use cosm_utils::{
     config::cfg::ChainConfig,
@@ -18,8 +20,10 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "ledger")]
 use std::rc::Rc;
 use std::{
+    collections::BTreeMap,
     fmt::Display,
     fs::{create_dir_all, OpenOptions},
+    future::Future,
     io::prelude::*,
     path::PathBuf,
     sync::Arc,
@@ -35,15 +39,56 @@ lazy_static! {
             None => panic!("WORKSPACE_SETTINGS not set"),
         }
     };
+    /// Tx options passed on the command line, applied on top of the env defaults
+    pub static ref TX_OVERRIDES: RwLock<TxConfig> = RwLock::new(TxConfig::default());
+    /// Key passed on the command line, signs every tx instead of the keys of the env
+    pub static ref KEY_OVERRIDE: RwLock<Option<String>> = RwLock::new(None);
+    /// Set when the msgs of the command are submitted in a governance proposal
+    pub static ref PROPOSAL: RwLock<Option<ProposalConfig>> = RwLock::new(None);
+}
+
+tokio::task_local! {
+    /// When set, the env that counts as active for the current task instead of the selected one.
+    /// This lets several envs be deployed to at once.
+    pub static ENV_OVERRIDE: String;
+
+    /// When set, the chain that counts as active for the current task instead of the chain of the env.
+    /// Used to reach contracts that are pinned to another chain.
+    pub static CHAIN_OVERRIDE: String;
+}
+
+/// Runs `fut` with the chain of the given contract as the active chain.
+pub async fn on_contract_chain<F: Future>(name: &str, fut: F) -> anyhow::Result<F::Output> {
+    let chain_id = CONFIG.read().await.contract_chain_id(name)?;
+    Ok(CHAIN_OVERRIDE.scope(chain_id, fut).await)
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Env {
     pub is_active: bool,
     pub env_id: String,
+    /// Chain of every contract that is not pinned to another chain
     pub chain_id: String,
     pub contracts: Vec<ContractInfo>,
+    /// Key that signs for every role that is not bound to another key
     pub key_name: String,
+    /// Signer roles bound to key names, such as `deployer`, `admin` and `operator`
+    #[serde(default)]
+    pub roles: BTreeMap<String, String>,
+    /// Overrides the admin of every contract instantiated in this env
+    #[serde(default)]
+    pub admin: Option<String>,
+    /// Instantiates contracts in this env without an admin
+    #[serde(default)]
+    pub no_admin: bool,
+    /// Label of instantiated contracts, such as `myproj-{env_id}-{contract}-v{version}`.
+    /// Supports `{env_id}`, `{contract}`, `{version}` and `{git_sha}`.
+    /// Defaults to the contract name.
+    #[serde(default)]
+    pub label_template: Option<String>,
+    /// Default options for every tx broadcast in this env
+    #[serde(default)]
+    pub tx: TxConfig,
 }
 
 impl Display for Env {
@@ -52,11 +97,68 @@ impl Display for Env {
     }
 }
 
+impl Env {
+    /// Returns the name of the key bound to `role` in this env, or the key of the env.
+    /// Unlike `Config::role_key_name` this ignores the `--key` flag.
+    pub fn role_key_name(&self, role: Option<&str>) -> &str {
+        role.and_then(|role| self.roles.get(role))
+            .unwrap_or(&self.key_name)
+    }
+
+    /// Returns the admin a contract is instantiated with in this env,
+    /// or `None` if it should have no admin.
+    pub fn admin(&self, default: String) -> Option<String> {
+        if self.no_admin {
+            None
+        } else {
+            Some(self.admin.clone().unwrap_or(default))
+        }
+    }
+}
+
 #[derive(Clone, Debug, JsonSchema, PartialEq, Serialize, Deserialize)]
 pub struct ContractInfo {
     pub name: String,
     pub addr: Option<String>,
     pub code_id: Option<u64>,
+    /// Chain the contract is deployed on, defaults to the chain of the env
+    #[serde(default)]
+    pub chain_id: Option<String>,
+    /// Where the code behind `code_id` came from, filled in by store_code
+    #[serde(default)]
+    pub provenance: Option<CodeProvenance>,
+    /// The code ids the contract has run, oldest first
+    #[serde(default)]
+    pub code_id_history: Vec<CodeIdRecord>,
+}
+
+impl ContractInfo {
+    /// The code id the contract is running according to its history
+    pub fn current_code_id(&self) -> Option<u64> {
+        self.code_id_history.last().map(|x| x.code_id)
+    }
+
+    /// Records that the contract now runs `record.code_id`.
+    /// `previous_code_id` is the code id it ran before, which is added first
+    /// if the history does not already end with it.
+    pub fn push_code_id(&mut self, previous_code_id: u64, record: CodeIdRecord) {
+        if self.current_code_id() != Some(previous_code_id) {
+            self.code_id_history.push(CodeIdRecord {
+                code_id: previous_code_id,
+                migrate_msg: None,
+                tx_hash: None,
+            });
+        }
+        self.code_id_history.push(record);
+    }
+}
+
+#[derive(Clone, Debug, JsonSchema, PartialEq, Serialize, Deserialize)]
+pub struct CodeIdRecord {
+    pub code_id: u64,
+    /// The migrate msg that moved the contract to this code id
+    pub migrate_msg: Option<serde_json::Value>,
+    pub tx_hash: Option<String>,
 }
 
 impl Display for ContractInfo {
@@ -65,15 +167,39 @@ impl Display for ContractInfo {
     }
 }
 
+#[derive(Clone, Debug, JsonSchema, PartialEq, Serialize, Deserialize)]
+pub struct CodeProvenance {
+    /// Hex encoded sha256 of the optimized wasm
+    pub checksum: String,
+    pub optimized_size: u64,
+    pub gzipped_size: u64,
+    pub git_commit: Option<String>,
+    /// Whether the workspace had uncommitted changes when the code was built
+    pub git_dirty: Option<bool>,
+    /// Unix timestamp in seconds
+    pub stored_at: u64,
+    /// Address of the key that uploaded the code
+    pub stored_by: String,
+}
+
+#[allow(deprecated)]
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct UserSettings {
+    /// Ignored, txs are now split by their simulated gas and size
+    #[deprecated(note = "txs are split by simulated gas and size, see `TxConfig::max_tx_bytes`")]
+    #[serde(default = "default_store_code_chunk_size")]
     pub store_code_chunk_size: usize,
 }
 
+fn default_store_code_chunk_size() -> usize {
+    2
+}
+
+#[allow(deprecated)]
 impl Default for UserSettings {
     fn default() -> Self {
         UserSettings {
-            store_code_chunk_size: 2,
+            store_code_chunk_size: default_store_code_chunk_size(),
         }
     }
 }
@@ -86,6 +212,10 @@ pub struct Config {
     pub chains: Vec<ChainInfo>,
     pub envs: Vec<Env>,
     pub keys: Vec<SigningKey>,
+    /// Set once a dry run has put placeholder code ids or addresses in the config,
+    /// so that it is never saved.
+    #[serde(skip)]
+    pub dry_run: bool,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
@@ -112,6 +242,9 @@ impl Config {
     }
 
     pub fn save(&self, settings: &WorkspaceSettings) -> anyhow::Result<()> {
+        if self.dry_run {
+            return Ok(());
+        }
         let mut file = OpenOptions::new()
             .truncate(true)
             .write(true)
@@ -122,36 +255,79 @@ impl Config {
         Ok(())
     }
 
+    fn active_env_index(&self) -> Option<usize> {
+        match ENV_OVERRIDE.try_with(|env_id| env_id.clone()) {
+            Ok(env_id) => self.envs.iter().position(|x| x.env_id == env_id),
+            Err(_) => self.envs.iter().position(|x| x.is_active),
+        }
+    }
+
     pub fn get_active_env(&self) -> Result<&Env, DeployError> {
-        match self.envs.iter().position(|x| x.is_active) {
+        match self.active_env_index() {
             Some(index) => Ok(self.envs.get(index).ok_or(DeployError::EnvNotFound)?),
             None => Err(DeployError::EnvNotFound),
         }
     }
 
     pub fn get_active_env_mut(&mut self) -> anyhow::Result<&mut Env> {
-        match self.envs.iter().position(|x| x.is_active) {
+        match self.active_env_index() {
             Some(index) => Ok(self.envs.get_mut(index).ok_or(DeployError::EnvNotFound)?),
             None => Err(DeployError::EnvNotFound.into()),
         }
     }
 
     pub fn get_active_chain_info(&self) -> anyhow::Result<&ChainInfo> {
-        let env = self.get_active_env()?;
-        match self.chains.iter().find(|x| x.cfg.chain_id == env.chain_id) {
+        match CHAIN_OVERRIDE.try_with(|chain_id| chain_id.clone()) {
+            Ok(chain_id) => self.get_chain_info(&chain_id),
+            Err(_) => self.get_chain_info(&self.get_active_env()?.chain_id),
+        }
+    }
+
+    pub fn get_chain_info(&self, chain_id: &str) -> anyhow::Result<&ChainInfo> {
+        match self.chains.iter().find(|x| x.cfg.chain_id == chain_id) {
             Some(chain_info) => Ok(chain_info),
             None => Err(DeployError::ChainConfigNotFound.into()),
         }
     }
 
-    #[allow(unused_mut)]
+    /// Returns the chain a contract is pinned to, or the chain of the active env.
+    pub fn contract_chain_id(&self, name: &str) -> anyhow::Result<String> {
+        let env = self.get_active_env()?;
+        Ok(env
+            .contracts
+            .iter()
+            .find(|x| x.name == name)
+            .and_then(|x| x.chain_id.clone())
+            .unwrap_or(env.chain_id.clone()))
+    }
+
     pub async fn get_active_key(&self) -> anyhow::Result<SigningKey> {
-        let active_key_name = self.get_active_env()?.key_name.clone();
-        let key = self.keys.iter().find(|x| x.name == active_key_name).ok_or(
-            DeployError::KeyNotFound {
-                key_name: active_key_name,
-            },
-        )?;
+        self.get_role_key(None).await
+    }
+
+    /// Returns the name of the key that signs for `role` in the active env.
+    /// `None` and roles that are not bound fall back to the key of the env,
+    /// and the `--key` flag overrides every role.
+    pub async fn role_key_name(&self, role: Option<&str>) -> anyhow::Result<String> {
+        if let Some(key_name) = KEY_OVERRIDE.read().await.clone() {
+            return Ok(key_name);
+        }
+        Ok(self.get_active_env()?.role_key_name(role).to_string())
+    }
+
+    pub async fn get_role_key(&self, role: Option<&str>) -> anyhow::Result<SigningKey> {
+        self.get_key(&self.role_key_name(role).await?).await
+    }
+
+    #[allow(unused_mut)]
+    pub async fn get_key(&self, key_name: &str) -> anyhow::Result<SigningKey> {
+        let key =
+            self.keys
+                .iter()
+                .find(|x| x.name == key_name)
+                .ok_or(DeployError::KeyNotFound {
+                    key_name: key_name.to_string(),
+                })?;
         let mut key = key.clone();
         #[cfg(feature = "ledger")]
         if let Key::Ledger { connection, .. } = &mut key.key {
@@ -352,9 +528,14 @@ impl Config {
         let env = Env {
             is_active: true,
             key_name,
+            roles: BTreeMap::new(),
             env_id,
             chain_id,
             contracts: vec![],
+            admin: None,
+            no_admin: false,
+            label_template: None,
+            tx: TxConfig::default(),
         };
         self.envs.push(env);
         if self.envs.len() > 1 {
@@ -0,0 +1,146 @@
+use std::{
+    fs::{create_dir_all, OpenOptions},
+    io::prelude::*,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use colored::Colorize;
+use cosm_utils::{clients::client::GetEvents, modules::auth::model::Address};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tendermint_rpc::endpoint::broadcast::tx_commit;
+
+use crate::{file::Config, settings::WorkspaceSettings, utils::get_settings};
+
+/// A tx that wasm-deploy broadcast, as recorded in the audit log.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Unix timestamp in seconds
+    pub timestamp: u64,
+    pub env_id: String,
+    pub chain_id: String,
+    pub signer: String,
+    pub stage: String,
+    pub contracts: Vec<String>,
+    pub msgs: Vec<Value>,
+    pub tx_hash: String,
+    pub height: u64,
+    pub gas_wanted: i64,
+    pub gas_used: i64,
+    /// Taken from the `tx` event, if the chain emits one
+    pub fee: Option<String>,
+}
+
+impl HistoryEntry {
+    /// Default path is `.wasm-deploy/history/<env_id>.jsonl`
+    pub fn path(settings: &WorkspaceSettings, env_id: &str) -> PathBuf {
+        settings
+            .config_path
+            .parent()
+            .expect("Invalid CONFIG_PATH")
+            .join("history")
+            .join(format!("{env_id}.jsonl"))
+    }
+
+    /// Appends the entry to the audit log of its env.
+    pub fn append(&self, settings: &WorkspaceSettings) -> anyhow::Result<()> {
+        let path = HistoryEntry::path(settings, &self.env_id);
+        create_dir_all(path.parent().expect("Invalid history path"))?;
+        let mut file = OpenOptions::new().append(true).create(true).open(path)?;
+        let mut serialized = serde_json::to_vec(self)?;
+        serialized.push(b'\n');
+        file.write_all(&serialized)?;
+        Ok(())
+    }
+
+    pub fn load_all(
+        settings: &WorkspaceSettings,
+        env_id: &str,
+    ) -> anyhow::Result<Vec<HistoryEntry>> {
+        match std::fs::read_to_string(HistoryEntry::path(settings, env_id)) {
+            Ok(serialized) => Ok(serialized
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(serde_json::from_str)
+                .collect::<Result<Vec<_>, _>>()?),
+            Err(_) => Ok(vec![]),
+        }
+    }
+}
+
+/// Appends a broadcast tx to the audit log of the active env.
+/// `signer` is the address that signed the tx,
+/// and `msgs` pairs each msg with the name of the contract it was sent to.
+pub async fn record_tx(
+    config: &Config,
+    stage: &str,
+    signer: &Address,
+    msgs: &[(String, Value)],
+    res: &tx_commit::Response,
+) -> anyhow::Result<()> {
+    let settings = get_settings().await?;
+    let env = config.get_active_env()?;
+    let chain_info = config.get_active_chain_info()?;
+    let mut contracts: Vec<String> = vec![];
+    for (contract, _) in msgs {
+        if !contracts.contains(contract) {
+            contracts.push(contract.clone());
+        }
+    }
+    let fee = res
+        .find_event_tags("tx".to_string(), "fee".to_string())
+        .first()
+        .map(|x| x.value.to_string());
+    HistoryEntry {
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+        env_id: env.env_id.clone(),
+        chain_id: chain_info.cfg.chain_id.clone(),
+        signer: signer.to_string(),
+        stage: stage.to_string(),
+        contracts,
+        msgs: msgs.iter().map(|(_, msg)| msg.clone()).collect(),
+        tx_hash: res.hash.to_string(),
+        height: res.height.value(),
+        gas_wanted: res.deliver_tx.gas_wanted,
+        gas_used: res.deliver_tx.gas_used,
+        fee,
+    }
+    .append(&settings)
+}
+
+/// Prints the audit log of the active env, optionally filtered by contract and stage.
+pub async fn print_history(
+    settings: &WorkspaceSettings,
+    config: &Config,
+    contracts: &[String],
+    stage: Option<&str>,
+) -> anyhow::Result<()> {
+    let env_id = &config.get_active_env()?.env_id;
+    let entries = HistoryEntry::load_all(settings, env_id)?
+        .into_iter()
+        .filter(|entry| {
+            contracts.is_empty() || entry.contracts.iter().any(|x| contracts.contains(x))
+        })
+        .filter(|entry| stage.is_none() || stage == Some(entry.stage.as_str()));
+    for entry in entries {
+        println!(
+            "{} {} {}",
+            entry.timestamp,
+            entry.stage.bold(),
+            entry.contracts.join(", ")
+        );
+        println!(
+            "  tx hash: {}, height: {}",
+            entry.tx_hash.purple(),
+            entry.height
+        );
+        println!(
+            "  gas wanted: {}, gas used: {}, fee: {}",
+            entry.gas_wanted.to_string().green(),
+            entry.gas_used.to_string().green(),
+            entry.fee.unwrap_or("unknown".into()).green()
+        );
+    }
+    Ok(())
+}
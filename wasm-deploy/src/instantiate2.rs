@@ -0,0 +1,159 @@
+use cosm_utils::{
+    chain::{coin::Coin, msg::Msg},
+    modules::{auth::model::Address, cosmwasm::error::CosmwasmError},
+};
+use cosmrs::proto::{cosmos::base::v1beta1::Coin as ProtoCoin, traits::TypeUrl};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tendermint_rpc::HttpClient;
+
+use crate::{checksum::query_code_checksum, error::DeployError};
+
+/// MsgInstantiateContract2 creates a new smart contract instance at an address
+/// derived from the code checksum, the creator and a salt.
+/// cosmos-sdk-proto does not register a type url for it, so it is declared here.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct MsgInstantiateContract2 {
+    #[prost(string, tag = "1")]
+    pub sender: String,
+    #[prost(string, tag = "2")]
+    pub admin: String,
+    #[prost(uint64, tag = "3")]
+    pub code_id: u64,
+    #[prost(string, tag = "4")]
+    pub label: String,
+    #[prost(bytes = "vec", tag = "5")]
+    pub msg: Vec<u8>,
+    #[prost(message, repeated, tag = "6")]
+    pub funds: Vec<ProtoCoin>,
+    #[prost(bytes = "vec", tag = "7")]
+    pub salt: Vec<u8>,
+    #[prost(bool, tag = "8")]
+    pub fix_msg: bool,
+}
+
+impl TypeUrl for MsgInstantiateContract2 {
+    const TYPE_URL: &'static str = "/cosmwasm.wasm.v1.MsgInstantiateContract2";
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Instantiate2Request<S: Serialize> {
+    pub code_id: u64,
+    pub msg: S,
+    pub label: String,
+    pub admin: Option<Address>,
+    pub funds: Vec<Coin>,
+    pub salt: Vec<u8>,
+}
+
+impl<S: Serialize> Instantiate2Request<S> {
+    pub fn to_proto(self, signer_addr: Address) -> serde_json::Result<Instantiate2RequestProto> {
+        let payload = serde_json::to_vec(&self.msg)?;
+
+        Ok(Instantiate2RequestProto {
+            signer_addr,
+            code_id: self.code_id,
+            msg: payload,
+            label: self.label,
+            admin: self.admin,
+            funds: self.funds,
+            salt: self.salt,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Instantiate2RequestProto {
+    pub signer_addr: Address,
+    pub code_id: u64,
+    pub msg: Vec<u8>,
+    pub label: String,
+    pub admin: Option<Address>,
+    pub funds: Vec<Coin>,
+    pub salt: Vec<u8>,
+}
+
+impl Msg for Instantiate2RequestProto {
+    type Proto = MsgInstantiateContract2;
+    type Err = CosmwasmError;
+}
+
+impl TryFrom<MsgInstantiateContract2> for Instantiate2RequestProto {
+    type Error = CosmwasmError;
+
+    fn try_from(msg: MsgInstantiateContract2) -> Result<Self, Self::Error> {
+        let admin = if msg.admin.is_empty() {
+            None
+        } else {
+            Some(msg.admin.parse()?)
+        };
+
+        Ok(Self {
+            signer_addr: msg.sender.parse()?,
+            code_id: msg.code_id,
+            msg: msg.msg,
+            label: msg.label,
+            admin,
+            funds: msg
+                .funds
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<Vec<_>, _>>()?,
+            salt: msg.salt,
+        })
+    }
+}
+
+impl TryFrom<Instantiate2RequestProto> for MsgInstantiateContract2 {
+    type Error = CosmwasmError;
+
+    fn try_from(req: Instantiate2RequestProto) -> Result<Self, Self::Error> {
+        Ok(Self {
+            sender: req.signer_addr.into(),
+            admin: req.admin.map(Into::into).unwrap_or_default(),
+            code_id: req.code_id,
+            label: req.label,
+            msg: req.msg,
+            funds: req.funds.into_iter().map(Into::into).collect(),
+            salt: req.salt,
+            fix_msg: false,
+        })
+    }
+}
+
+/// Derives the address that MsgInstantiateContract2 will assign to a contract.
+/// This matches `instantiate2_address` in cosmwasm-std and wasmd.
+pub fn instantiate2_address(
+    checksum: &[u8],
+    creator: &Address,
+    salt: &[u8],
+) -> anyhow::Result<Address> {
+    if salt.is_empty() || salt.len() > 64 {
+        return Err(DeployError::InvalidSalt.into());
+    }
+    let creator_bytes = creator.to_bytes();
+    let mut key = Vec::<u8>::new();
+    key.extend_from_slice(b"wasm\0");
+    for part in [checksum, &creator_bytes, salt, &[]] {
+        key.extend_from_slice(&(part.len() as u64).to_be_bytes());
+        key.extend_from_slice(part);
+    }
+    let address_data = Sha256::new()
+        .chain_update(Sha256::digest(b"module"))
+        .chain_update(key)
+        .finalize();
+    Ok(Address::new(creator.prefix(), &address_data)?)
+}
+
+/// Predicts the address of code that is already stored on chain.
+pub async fn predict_address(
+    client: &HttpClient,
+    code_id: u64,
+    creator: &Address,
+    salt: &[u8],
+) -> anyhow::Result<Address> {
+    let checksum = query_code_checksum(client, code_id)
+        .await?
+        .ok_or(DeployError::CodeIdNotFound)?;
+    instantiate2_address(&hex::decode(checksum)?, creator, salt)
+}
@@ -0,0 +1,100 @@
+use std::{
+    fs::{create_dir_all, remove_file, OpenOptions},
+    io::{prelude::*, ErrorKind},
+    path::PathBuf,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{contract::Deploy, file::ContractInfo, settings::WorkspaceSettings};
+
+/// A per env record of the deployment stages that have already landed on chain.
+/// `deploy --resume` uses it to skip everything that completed before a failure.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Journal {
+    pub env_id: String,
+    pub entries: Vec<JournalEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub stage: String,
+    pub contract: String,
+    pub tx_hash: Option<String>,
+    pub result: Option<ContractInfo>,
+}
+
+impl Journal {
+    pub fn new(env_id: &str) -> Journal {
+        Journal {
+            env_id: env_id.to_string(),
+            entries: vec![],
+        }
+    }
+
+    /// Default path is `.wasm-deploy/journal/<env_id>.json`
+    pub fn path(settings: &WorkspaceSettings, env_id: &str) -> PathBuf {
+        settings
+            .config_path
+            .parent()
+            .expect("Invalid CONFIG_PATH")
+            .join("journal")
+            .join(format!("{env_id}.json"))
+    }
+
+    pub fn load(settings: &WorkspaceSettings, env_id: &str) -> anyhow::Result<Journal> {
+        match std::fs::read(Journal::path(settings, env_id)) {
+            Ok(serialized) => Ok(serde_json::from_slice(&serialized)?),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Journal::new(env_id)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, settings: &WorkspaceSettings) -> anyhow::Result<()> {
+        let path = Journal::path(settings, &self.env_id);
+        create_dir_all(path.parent().expect("Invalid journal path"))?;
+        let mut file = OpenOptions::new()
+            .truncate(true)
+            .write(true)
+            .create(true)
+            .open(path)?;
+        let serialized = serde_json::to_vec_pretty(self)?;
+        file.write_all(&serialized)?;
+        Ok(())
+    }
+
+    pub fn clear(settings: &WorkspaceSettings, env_id: &str) -> anyhow::Result<()> {
+        let path = Journal::path(settings, env_id);
+        if path.exists() {
+            remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// Adds or replaces the entry for a stage and contract
+    pub fn record(&mut self, entry: JournalEntry) {
+        match self
+            .entries
+            .iter_mut()
+            .find(|x| x.stage == entry.stage && x.contract == entry.contract)
+        {
+            Some(existing) => *existing = entry,
+            None => self.entries.push(entry),
+        }
+    }
+
+    pub fn is_complete(&self, stage: &str, contract: &str) -> bool {
+        self.entries
+            .iter()
+            .any(|x| x.stage == stage && x.contract == contract)
+    }
+
+    /// Returns the contracts that have not yet completed the given stage
+    pub fn remaining<C: Deploy + Clone>(&self, stage: &str, contracts: &[C]) -> Vec<C> {
+        contracts
+            .iter()
+            .filter(|x| !self.is_complete(stage, &x.name()))
+            .cloned()
+            .collect()
+    }
+}
@@ -1,20 +1,33 @@
+pub mod admin;
+pub mod authz;
+pub mod checksum;
 pub mod cli;
 pub mod commands;
+pub mod context;
 pub mod contract;
 pub mod cw20;
 pub mod deployment;
 pub mod error;
 pub mod execute;
 pub mod file;
+pub mod history;
+pub mod instantiate2;
+pub mod journal;
+pub mod permissions;
+pub mod proposal;
 pub mod query;
 pub mod settings;
+pub mod tx;
+pub mod tx_file;
 pub mod utils;
+pub mod verify;
 
 pub use cosm_utils;
 
 #[cfg(wasm_cli)]
 pub mod wasm_cli;
 
+pub use async_trait::async_trait;
 pub use strum;
 pub use strum_macros;
 pub use wasm_deploy_derive as derive;
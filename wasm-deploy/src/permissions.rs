@@ -0,0 +1,138 @@
+use std::str::FromStr;
+
+use cosm_utils::{
+    chain::msg::Msg,
+    config::cfg::ChainConfig,
+    modules::{
+        auth::model::Address,
+        cosmwasm::{
+            error::CosmwasmError,
+            model::{AccessConfig, AccessType},
+        },
+    },
+    signing_key::key::SigningKey,
+};
+use cosmrs::proto::{cosmwasm::wasm::v1::AccessConfig as ProtoAccessConfig, traits::TypeUrl};
+use serde::{Deserialize, Serialize};
+use tendermint_rpc::{endpoint::broadcast::tx_commit, HttpClient};
+
+use crate::tx::{broadcast, TxConfig};
+
+/// Who is allowed to instantiate a stored code id.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InstantiatePermission {
+    Everybody,
+    Nobody,
+    AnyOfAddresses(Vec<String>),
+    /// Only the key that stores the code
+    Deployer,
+}
+
+impl InstantiatePermission {
+    pub fn to_access_config(&self, deployer: &Address) -> anyhow::Result<AccessConfig> {
+        let (permission, addresses) = match self {
+            InstantiatePermission::Everybody => (AccessType::Everybody, vec![]),
+            InstantiatePermission::Nobody => (AccessType::Nobody, vec![]),
+            InstantiatePermission::AnyOfAddresses(addresses) => (
+                AccessType::AnyOfAddresses,
+                addresses
+                    .iter()
+                    .map(|x| Ok(Address::from_str(x)?))
+                    .collect::<anyhow::Result<Vec<_>>>()?,
+            ),
+            InstantiatePermission::Deployer => (AccessType::AnyOfAddresses, vec![deployer.clone()]),
+        };
+        Ok(AccessConfig {
+            permission,
+            addresses,
+        })
+    }
+}
+
+/// MsgUpdateInstantiateConfig updates the instantiate permission of stored code.
+/// cosmos-sdk-proto does not include it, so it is declared here.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct MsgUpdateInstantiateConfig {
+    #[prost(string, tag = "1")]
+    pub sender: String,
+    #[prost(uint64, tag = "2")]
+    pub code_id: u64,
+    #[prost(message, optional, tag = "3")]
+    pub new_instantiate_permission: Option<ProtoAccessConfig>,
+}
+
+impl TypeUrl for MsgUpdateInstantiateConfig {
+    const TYPE_URL: &'static str = "/cosmwasm.wasm.v1.MsgUpdateInstantiateConfig";
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct UpdateInstantiateConfigRequest {
+    pub code_id: u64,
+    pub permission: AccessConfig,
+}
+
+impl UpdateInstantiateConfigRequest {
+    pub fn to_proto(self, signer_addr: Address) -> UpdateInstantiateConfigProto {
+        UpdateInstantiateConfigProto {
+            signer_addr,
+            code_id: self.code_id,
+            permission: self.permission,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct UpdateInstantiateConfigProto {
+    pub signer_addr: Address,
+    pub code_id: u64,
+    pub permission: AccessConfig,
+}
+
+impl Msg for UpdateInstantiateConfigProto {
+    type Proto = MsgUpdateInstantiateConfig;
+    type Err = CosmwasmError;
+}
+
+impl TryFrom<MsgUpdateInstantiateConfig> for UpdateInstantiateConfigProto {
+    type Error = CosmwasmError;
+
+    fn try_from(msg: MsgUpdateInstantiateConfig) -> Result<Self, Self::Error> {
+        Ok(Self {
+            signer_addr: msg.sender.parse()?,
+            code_id: msg.code_id,
+            permission: msg
+                .new_instantiate_permission
+                .unwrap_or_default()
+                .try_into()?,
+        })
+    }
+}
+
+impl TryFrom<UpdateInstantiateConfigProto> for MsgUpdateInstantiateConfig {
+    type Error = CosmwasmError;
+
+    fn try_from(req: UpdateInstantiateConfigProto) -> Result<Self, Self::Error> {
+        Ok(Self {
+            sender: req.signer_addr.into(),
+            code_id: req.code_id,
+            new_instantiate_permission: Some(req.permission.into()),
+        })
+    }
+}
+
+pub async fn update_instantiate_config_batch_commit(
+    client: &HttpClient,
+    chain_cfg: &ChainConfig,
+    reqs: Vec<UpdateInstantiateConfigRequest>,
+    key: &SigningKey,
+    tx_config: &TxConfig,
+) -> anyhow::Result<Option<tx_commit::Response>> {
+    let sender_addr = tx_config.sender_addr(key, chain_cfg).await?;
+
+    let msgs = reqs
+        .into_iter()
+        .map(|r| r.to_proto(sender_addr.clone()))
+        .collect::<Vec<_>>();
+
+    broadcast(client, chain_cfg, msgs, key, tx_config).await
+}
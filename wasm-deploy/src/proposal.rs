@@ -0,0 +1,174 @@
+use std::{io::Read, path::PathBuf};
+
+use clap::Args;
+use cosm_utils::{modules::auth::model::Address, prelude::*};
+use cosmrs::proto::{
+    cosmos::base::v1beta1::Coin as ProtoCoin, cosmwasm::wasm::v1::MsgStoreCode, traits::TypeUrl,
+    Any,
+};
+use flate2::read::GzDecoder;
+use prost::Message;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tendermint_rpc::HttpClient;
+
+use crate::{error::DeployError, tx::parse_coin, tx_file::msg_to_json};
+
+pub const MSG_SUBMIT_PROPOSAL: &str = "/cosmos.gov.v1.MsgSubmitProposal";
+
+/// `PROPOSAL_STATUS_PASSED` of gov v1
+pub const PROPOSAL_STATUS_PASSED: i32 = 3;
+
+/// Options of the governance proposal that the msgs of store_code, instantiate and migrate
+/// are submitted in, for chains where those msgs require governance.
+#[derive(Args, Clone, Debug, Default, PartialEq)]
+#[clap(rename_all = "snake_case")]
+pub struct ProposalConfig {
+    /// Submits the msgs in a governance proposal instead of sending them
+    #[arg(long, required = false)]
+    pub proposal: bool,
+
+    /// Title of the proposal, defaults to the stage and the names of the contracts
+    #[arg(long, requires = "proposal")]
+    pub title: Option<String>,
+
+    /// Summary of the proposal, defaults to the title.
+    /// The checksums of stored code are appended to it
+    #[arg(long, requires = "proposal")]
+    pub summary: Option<String>,
+
+    /// Initial deposit of the proposal, such as 1000000000uosmo
+    #[arg(long, requires = "proposal")]
+    pub deposit: Option<String>,
+
+    /// Account that executes the msgs once the proposal passes, defaults to the gov module account
+    #[arg(long, requires = "proposal")]
+    pub authority: Option<String>,
+
+    /// Writes the proposal as json for `tx gov submit-proposal` instead of broadcasting it
+    #[arg(long, requires = "proposal")]
+    pub proposal_file: Option<PathBuf>,
+}
+
+impl ProposalConfig {
+    /// The address the proposal msgs are sent from.
+    pub fn authority(&self, prefix: &str) -> anyhow::Result<Address> {
+        match &self.authority {
+            Some(authority) => Ok(authority.parse()?),
+            None => gov_module_address(prefix),
+        }
+    }
+
+    pub fn initial_deposit(&self) -> anyhow::Result<Vec<ProtoCoin>> {
+        self.deposit
+            .iter()
+            .map(|deposit| {
+                let coin = parse_coin(deposit)?;
+                Ok(ProtoCoin {
+                    denom: coin.denom.to_string(),
+                    amount: coin.amount.to_string(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Address of the gov module account, which is the authority of passed proposals on most chains.
+pub fn gov_module_address(prefix: &str) -> anyhow::Result<Address> {
+    Ok(Address::new(prefix, &Sha256::digest(b"gov")[..20])?)
+}
+
+/// MsgSubmitProposal of gov v1 submits a proposal that executes `messages` once it passes.
+/// cosmos-sdk-proto only includes gov v1beta1, so it is declared here.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct MsgSubmitProposal {
+    #[prost(message, repeated, tag = "1")]
+    pub messages: Vec<Any>,
+    #[prost(message, repeated, tag = "2")]
+    pub initial_deposit: Vec<ProtoCoin>,
+    #[prost(string, tag = "3")]
+    pub proposer: String,
+    #[prost(string, tag = "4")]
+    pub metadata: String,
+    #[prost(string, tag = "5")]
+    pub title: String,
+    #[prost(string, tag = "6")]
+    pub summary: String,
+}
+
+impl TypeUrl for MsgSubmitProposal {
+    const TYPE_URL: &'static str = MSG_SUBMIT_PROPOSAL;
+}
+
+/// Proposal of gov v1, with only the fields that wasm-deploy reads.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Proposal {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+    #[prost(message, repeated, tag = "2")]
+    pub messages: Vec<Any>,
+    #[prost(int32, tag = "3")]
+    pub status: i32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct QueryProposalRequest {
+    #[prost(uint64, tag = "1")]
+    pub proposal_id: u64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct QueryProposalResponse {
+    #[prost(message, optional, tag = "1")]
+    pub proposal: Option<Proposal>,
+}
+
+/// The proposal in the json format read by `tx gov submit-proposal`.
+pub fn proposal_json(msg: &MsgSubmitProposal) -> anyhow::Result<Value> {
+    Ok(json!({
+        "messages": msg.messages.iter().map(msg_to_json).collect::<anyhow::Result<Vec<_>>>()?,
+        "metadata": msg.metadata,
+        "deposit": msg
+            .initial_deposit
+            .iter()
+            .map(|x| format!("{}{}", x.amount, x.denom))
+            .collect::<Vec<_>>()
+            .join(","),
+        "title": msg.title,
+        "summary": msg.summary,
+    }))
+}
+
+pub async fn query_proposal(client: &HttpClient, proposal_id: u64) -> anyhow::Result<Proposal> {
+    let res = client
+        .query::<_, QueryProposalResponse>(
+            QueryProposalRequest { proposal_id },
+            "/cosmos.gov.v1.Query/Proposal",
+        )
+        .await?;
+    Ok(res
+        .proposal
+        .ok_or(DeployError::ProposalNotFound { proposal_id })?)
+}
+
+/// The checksums of the code stored by a proposal, paired with the address that stored it.
+pub fn stored_checksums(proposal: &Proposal) -> anyhow::Result<Vec<(String, String)>> {
+    proposal
+        .messages
+        .iter()
+        .filter(|any| any.type_url == MsgStoreCode::TYPE_URL)
+        .map(|any| {
+            let msg = MsgStoreCode::decode(any.value.as_slice())?;
+            // Code is usually uploaded gzipped, while the checksum is of the wasm itself
+            let wasm = match msg.wasm_byte_code.starts_with(&[0x1f, 0x8b]) {
+                true => {
+                    let mut wasm = vec![];
+                    GzDecoder::new(msg.wasm_byte_code.as_slice()).read_to_end(&mut wasm)?;
+                    wasm
+                }
+                false => msg.wasm_byte_code,
+            };
+            Ok((hex::encode(Sha256::digest(wasm)), msg.sender))
+        })
+        .collect()
+}
@@ -2,6 +2,9 @@ use std::str::FromStr;
 
 use colored_json::to_colored_json_auto;
 use cosm_utils::{modules::auth::model::Address, prelude::*};
+use cosmrs::proto::cosmwasm::wasm::v1::{
+    ContractInfo, QueryContractInfoRequest, QueryContractInfoResponse,
+};
 use cw20::Cw20QueryMsg;
 use inquire::Text;
 use interactive_parse::InteractiveParseObj;
@@ -26,6 +29,22 @@ pub async fn query_contract(contract: &impl Deploy) -> anyhow::Result<Value> {
     Ok(value)
 }
 
+/// Queries the on chain info of a contract, such as its code id and admin.
+pub async fn query_contract_info(
+    client: &HttpClient,
+    address: &str,
+) -> anyhow::Result<Option<ContractInfo>> {
+    let res = client
+        .query::<_, QueryContractInfoResponse>(
+            QueryContractInfoRequest {
+                address: address.to_string(),
+            },
+            "/cosmwasm.wasm.v1.Query/ContractInfo",
+        )
+        .await?;
+    Ok(res.contract_info)
+}
+
 pub async fn query(
     config: &Config,
     mut addr: impl AsRef<str> + Serialize + DeserializeOwned + Clone,
@@ -0,0 +1,489 @@
+use std::{collections::VecDeque, ops::Range, path::PathBuf};
+
+use clap::Args;
+use colored::Colorize;
+use cosm_utils::{
+    chain::{coin::Coin, error::ChainError, fee::Fee, msg::Msg, request::TxOptions},
+    config::cfg::ChainConfig,
+    modules::auth::model::Address,
+    prelude::*,
+    signing_key::key::SigningKey,
+};
+use serde::{Deserialize, Serialize};
+use tendermint_rpc::{endpoint::broadcast::tx_commit, Client, HttpClient};
+
+use crate::{
+    authz::exec_msg,
+    error::DeployError,
+    file::{Config, CONFIG, TX_OVERRIDES},
+    tx_file::{EncodedMsg, TxFile},
+    utils::get_settings,
+};
+
+/// Options for every tx that wasm-deploy broadcasts.
+/// Defaults are set per env and can be overridden with the global command line flags.
+/// Options that only apply to a single invocation are command line only and are not saved with the env.
+#[derive(Args, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TxConfig {
+    /// Memo added to every tx
+    #[arg(long, global = true)]
+    pub memo: Option<String>,
+
+    /// Gas limit of every tx, skips the simulation
+    #[arg(long, global = true)]
+    pub gas_limit: Option<u64>,
+
+    /// Overrides the gas adjustment of the chain
+    #[arg(long, global = true)]
+    pub gas_adjustment: Option<f64>,
+
+    /// Fee paid for every tx, such as 5000uosmo. Defaults to the gas price times the gas used
+    #[arg(long, global = true)]
+    pub fee_amount: Option<String>,
+
+    /// Address that pays the fee through a fee grant
+    #[arg(long, global = true)]
+    pub fee_granter: Option<String>,
+
+    /// Block height after which the tx will not be processed
+    #[arg(long, global = true)]
+    pub timeout_height: Option<u64>,
+
+    /// Largest tx the node accepts, batches are split to stay below it.
+    /// Defaults to the CometBFT mempool default of 1 MiB
+    #[arg(long, global = true)]
+    #[serde(skip)]
+    pub max_tx_bytes: Option<u64>,
+
+    /// Writes every tx to an unsigned tx file instead of signing and broadcasting it
+    #[arg(long, global = true)]
+    #[serde(skip)]
+    pub generate_only: bool,
+
+    /// Address the generated txs are sent from, such as a multisig.
+    /// Defaults to the address of the signing key
+    #[arg(long, global = true, requires = "generate_only")]
+    #[serde(skip)]
+    pub from: Option<String>,
+
+    /// Address that msgs are sent from through authz.
+    /// Every msg is wrapped in a MsgExec that the key signs as the grantee
+    #[arg(long, global = true)]
+    #[serde(skip)]
+    pub authz_granter: Option<String>,
+}
+
+impl TxConfig {
+    /// Returns this config with every option that is set in `overrides` replaced.
+    /// The command line only options are always taken from `overrides`.
+    pub fn merge(&self, overrides: &TxConfig) -> TxConfig {
+        TxConfig {
+            memo: overrides.memo.clone().or(self.memo.clone()),
+            gas_limit: overrides.gas_limit.or(self.gas_limit),
+            gas_adjustment: overrides.gas_adjustment.or(self.gas_adjustment),
+            fee_amount: overrides.fee_amount.clone().or(self.fee_amount.clone()),
+            fee_granter: overrides.fee_granter.clone().or(self.fee_granter.clone()),
+            timeout_height: overrides.timeout_height.or(self.timeout_height),
+            max_tx_bytes: overrides.max_tx_bytes,
+            generate_only: overrides.generate_only,
+            from: overrides.from.clone(),
+            authz_granter: overrides.authz_granter.clone(),
+        }
+    }
+
+    /// The address msgs are sent from, which is the authz granter if there is one
+    /// and otherwise the signer of the tx.
+    pub async fn sender_addr(
+        &self,
+        key: &SigningKey,
+        chain_cfg: &ChainConfig,
+    ) -> anyhow::Result<Address> {
+        match &self.authz_granter {
+            Some(granter) => Ok(granter.parse()?),
+            None => self.signer_addr(key, chain_cfg).await,
+        }
+    }
+
+    /// The address that signs txs, which is `from` when generating txs and otherwise the key.
+    pub async fn signer_addr(
+        &self,
+        key: &SigningKey,
+        chain_cfg: &ChainConfig,
+    ) -> anyhow::Result<Address> {
+        match &self.from {
+            Some(from) if self.generate_only => Ok(from.parse()?),
+            _ => Ok(key
+                .to_addr(&chain_cfg.prefix, &chain_cfg.derivation_path)
+                .await?),
+        }
+    }
+
+    /// Builds the options for a tx containing `msgs`.
+    /// The fee is left to cosm-utils unless one of the fee options is set,
+    /// in which case the gas is simulated here when no gas limit is given.
+    pub async fn tx_options<T>(
+        &self,
+        client: &HttpClient,
+        chain_cfg: &ChainConfig,
+        signer_addr: &Address,
+        msgs: &[T],
+    ) -> anyhow::Result<TxOptions>
+    where
+        T: Msg,
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        let default = TxOptions::default();
+        let fee = if self.gas_limit.is_some()
+            || self.gas_adjustment.is_some()
+            || self.fee_amount.is_some()
+            || self.fee_granter.is_some()
+        {
+            let mut fee = match self.gas_limit {
+                Some(gas_limit) => Fee {
+                    amount: vec![Coin {
+                        denom: chain_cfg.denom.parse()?,
+                        amount: (gas_limit as f64 * chain_cfg.gas_price).ceil() as u128,
+                    }],
+                    gas_limit: gas_limit.into(),
+                    payer: None,
+                    granter: None,
+                },
+                None => self.simulate(client, chain_cfg, signer_addr, msgs).await?,
+            };
+            if let Some(amount) = &self.fee_amount {
+                fee.amount = vec![parse_coin(amount)?];
+            }
+            if let Some(granter) = &self.fee_granter {
+                fee.granter = Some(granter.parse()?);
+            }
+            Some(fee)
+        } else {
+            None
+        };
+        Ok(TxOptions {
+            timeout_height: self.timeout_height.or(default.timeout_height),
+            fee,
+            account: None,
+            memo: self.memo.clone().unwrap_or(default.memo),
+        })
+    }
+
+    /// Encodes `msgs`, wrapping each one in a MsgExec from `signer_addr` when there is an authz granter.
+    /// Each msg gets its own MsgExec so that events keep the index of their msg.
+    pub fn authz_wrap<T>(
+        &self,
+        msgs: &[T],
+        signer_addr: &Address,
+    ) -> anyhow::Result<Vec<EncodedMsg>>
+    where
+        T: Msg,
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        let mut wrapped = vec![];
+        for msg in msgs {
+            let any = msg.to_any()?;
+            wrapped.push(EncodedMsg(match self.authz_granter {
+                Some(_) => exec_msg(signer_addr, any),
+                None => any,
+            }));
+        }
+        Ok(wrapped)
+    }
+
+    /// Simulates a tx containing `msgs` and returns the fee it would pay.
+    pub async fn simulate<T>(
+        &self,
+        client: &HttpClient,
+        chain_cfg: &ChainConfig,
+        signer_addr: &Address,
+        msgs: &[T],
+    ) -> anyhow::Result<Fee>
+    where
+        T: Msg,
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        let account = Auth::auth_query_account(client, signer_addr.clone())
+            .await?
+            .account;
+        let mut anys = vec![];
+        for msg in msgs {
+            anys.push(msg.to_any()?);
+        }
+        Ok(client
+            .tx_simulate(
+                &chain_cfg.denom,
+                chain_cfg.gas_price,
+                self.gas_adjustment.unwrap_or(chain_cfg.gas_adjustment),
+                anys,
+                &account,
+            )
+            .await?)
+    }
+}
+
+/// Parses a coin such as `5000uosmo`.
+pub fn parse_coin(coin: &str) -> anyhow::Result<Coin> {
+    let invalid = || DeployError::InvalidCoin {
+        coin: coin.to_string(),
+    };
+    let split = coin
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(invalid)?;
+    let (amount, denom) = coin.split_at(split);
+    Ok(Coin {
+        denom: denom.parse()?,
+        amount: amount.parse().map_err(|_| invalid())?,
+    })
+}
+
+/// Returns the tx config of the active env with the command line overrides applied.
+pub async fn tx_config(config: &Config) -> anyhow::Result<TxConfig> {
+    Ok(config
+        .get_active_env()?
+        .tx
+        .merge(&*TX_OVERRIDES.read().await))
+}
+
+/// Signs and broadcasts a tx containing `msgs` using the given tx config.
+/// With `generate_only` the tx is written to an unsigned tx file instead and `None` is returned.
+pub async fn broadcast<T>(
+    client: &HttpClient,
+    chain_cfg: &ChainConfig,
+    msgs: Vec<T>,
+    key: &SigningKey,
+    tx_config: &TxConfig,
+) -> anyhow::Result<Option<tx_commit::Response>>
+where
+    T: Msg + Serialize + Send + Sync,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    let signer_addr = tx_config.signer_addr(key, chain_cfg).await?;
+    let msgs = tx_config.authz_wrap(&msgs, &signer_addr)?;
+    if tx_config.generate_only {
+        generate(client, chain_cfg, &signer_addr, &msgs, tx_config).await?;
+        return Ok(None);
+    }
+    let tx_options = tx_config
+        .tx_options(client, chain_cfg, &signer_addr, &msgs)
+        .await?;
+    let tx_raw = client.tx_sign(chain_cfg, msgs, key, &tx_options).await?;
+    let response = Client::broadcast_tx_commit(client, tx_raw.to_bytes()?).await?;
+    // Checked here rather than by cosm-utils so that the error keeps its code
+    for (code, codespace, log) in [
+        (
+            response.check_tx.code,
+            &response.check_tx.codespace,
+            &response.check_tx.log,
+        ),
+        (
+            response.deliver_tx.code,
+            &response.deliver_tx.codespace,
+            &response.deliver_tx.log,
+        ),
+    ] {
+        if code.is_err() {
+            return Err(DeployError::TxFailed {
+                code: code.value(),
+                codespace: codespace.clone(),
+                log: log.clone(),
+                tx_hash: response.hash.to_string(),
+            }
+            .into());
+        }
+    }
+    Ok(Some(response))
+}
+
+/// Writes a tx containing `msgs` to an unsigned tx file of the active env and returns its path.
+/// The fee is simulated for `signer_addr` unless the tx config sets one.
+pub async fn generate<T>(
+    client: &HttpClient,
+    chain_cfg: &ChainConfig,
+    signer_addr: &Address,
+    msgs: &[T],
+    tx_config: &TxConfig,
+) -> anyhow::Result<PathBuf>
+where
+    T: Msg,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    let tx_options = tx_config
+        .tx_options(client, chain_cfg, signer_addr, msgs)
+        .await?;
+    let fee = match tx_options.fee {
+        Some(fee) => fee,
+        None => {
+            tx_config
+                .simulate(client, chain_cfg, signer_addr, msgs)
+                .await?
+        }
+    };
+    let mut anys = vec![];
+    for msg in msgs {
+        anys.push(msg.to_any()?);
+    }
+    let tx = TxFile::unsigned(
+        anys,
+        tx_options.memo,
+        tx_options.timeout_height.unwrap_or_default(),
+        cosmrs::tx::Fee::try_from(fee)?.into(),
+    );
+    let env_id = CONFIG.read().await.get_active_env()?.env_id.clone();
+    let path = TxFile::generated_path(&*get_settings().await?, &env_id)?;
+    tx.save(&path)?;
+    println!(
+        "Unsigned tx with {} msgs from {} written to {}",
+        msgs.len(),
+        signer_addr.to_string().purple(),
+        path.display()
+    );
+    Ok(path)
+}
+
+/// Default max tx bytes of the CometBFT mempool
+const DEFAULT_MAX_TX_BYTES: u64 = 1024 * 1024;
+
+/// Bytes set aside for the auth info, signature and memo of a tx
+const TX_OVERHEAD_BYTES: u64 = 1024;
+
+/// A tx sent by `broadcast_batched`, containing `msgs[range]` of the msgs it was given.
+pub struct BatchResponse {
+    pub range: Range<usize>,
+    pub response: tx_commit::Response,
+}
+
+/// Broadcasts `msgs` in as few txs as possible, in order.
+/// A batch is split in half while its simulated gas exceeds the block gas limit
+/// or its size exceeds the max tx bytes, and again if the chain rejects it
+/// for running out of gas or being too large.
+/// With `generate_only` all msgs are written to a single unsigned tx file and nothing is broadcast.
+/// If a batch fails, the batches that were already committed are returned along with the error.
+pub async fn broadcast_batched<T>(
+    client: &HttpClient,
+    chain_cfg: &ChainConfig,
+    msgs: Vec<T>,
+    key: &SigningKey,
+    tx_config: &TxConfig,
+) -> Result<Vec<BatchResponse>, (Vec<BatchResponse>, anyhow::Error)>
+where
+    T: Msg + Serialize + Send + Sync,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    let mut responses = vec![];
+    match send_batches(client, chain_cfg, msgs, key, tx_config, &mut responses).await {
+        Ok(()) => Ok(responses),
+        Err(e) => Err((responses, e)),
+    }
+}
+
+/// Does the work of `broadcast_batched`, pushing each committed batch to `responses`.
+async fn send_batches<T>(
+    client: &HttpClient,
+    chain_cfg: &ChainConfig,
+    msgs: Vec<T>,
+    key: &SigningKey,
+    tx_config: &TxConfig,
+    responses: &mut Vec<BatchResponse>,
+) -> anyhow::Result<()>
+where
+    T: Msg + Serialize + Send + Sync,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    let signer_addr = tx_config.signer_addr(key, chain_cfg).await?;
+    if tx_config.generate_only {
+        let msgs = tx_config.authz_wrap(&msgs, &signer_addr)?;
+        generate(client, chain_cfg, &signer_addr, &msgs, tx_config).await?;
+        return Ok(());
+    }
+    let block = client
+        .latest_consensus_params()
+        .await?
+        .consensus_params
+        .block;
+    let max_tx_bytes = tx_config
+        .max_tx_bytes
+        .unwrap_or(DEFAULT_MAX_TX_BYTES)
+        .min(block.max_bytes);
+    // A max gas of -1 means blocks have no gas limit.
+    // An explicit gas limit is used as is, so there is nothing to simulate.
+    let max_gas = u64::try_from(block.max_gas)
+        .ok()
+        .filter(|_| tx_config.gas_limit.is_none());
+    let mut sizes = vec![];
+    for msg in tx_config.authz_wrap(&msgs, &signer_addr)? {
+        sizes.push(msg.0.value.len() as u64);
+    }
+
+    let mut pending = VecDeque::new();
+    pending.push_back(0..msgs.len());
+    while let Some(range) = pending.pop_front() {
+        if range.is_empty() {
+            continue;
+        }
+        let batch = msgs[range.clone()].to_vec();
+        if range.len() > 1 {
+            let size = sizes[range.clone()].iter().sum::<u64>() + TX_OVERHEAD_BYTES;
+            let too_large = size > max_tx_bytes
+                || match max_gas {
+                    Some(max_gas) => {
+                        let wrapped = tx_config.authz_wrap(&batch, &signer_addr)?;
+                        match tx_config
+                            .simulate(client, chain_cfg, &signer_addr, &wrapped)
+                            .await
+                        {
+                            Ok(fee) => fee.gas_limit.value() > max_gas,
+                            Err(e) if exceeds_limits(&e) => true,
+                            Err(e) => return Err(e),
+                        }
+                    }
+                    None => false,
+                };
+            if too_large {
+                split(&mut pending, range);
+                continue;
+            }
+        }
+        match broadcast(client, chain_cfg, batch, key, tx_config).await {
+            Ok(Some(response)) => responses.push(BatchResponse { range, response }),
+            Ok(None) => {}
+            Err(e) if range.len() > 1 && exceeds_limits(&e) => {
+                println!(
+                    "{}",
+                    "Tx exceeded the gas or size limit, splitting it".yellow()
+                );
+                split(&mut pending, range);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// Queues the two halves of `range` to be sent next.
+fn split(pending: &mut VecDeque<Range<usize>>, range: Range<usize>) {
+    let mid = range.start + range.len() / 2;
+    pending.push_front(mid..range.end);
+    pending.push_front(range.start..mid);
+}
+
+/// Codespace of the errors defined by the Cosmos SDK itself
+const SDK_CODESPACE: &str = "sdk";
+/// `ErrOutOfGas` of the Cosmos SDK
+const ERR_OUT_OF_GAS: u32 = 11;
+/// `ErrTxTooLarge` of the Cosmos SDK
+const ERR_TX_TOO_LARGE: u32 = 21;
+
+/// Whether a tx or its simulation was rejected for running out of gas or being too large.
+fn exceeds_limits(e: &anyhow::Error) -> bool {
+    let (code, codespace) = if let Some(DeployError::TxFailed {
+        code, codespace, ..
+    }) = e.downcast_ref::<DeployError>()
+    {
+        (*code, codespace.as_str())
+    } else if let Some(ChainError::AbciQuery { res }) = e.downcast_ref::<ChainError>() {
+        (res.code.value(), res.codespace.as_str())
+    } else {
+        return false;
+    };
+    codespace == SDK_CODESPACE && matches!(code, ERR_OUT_OF_GAS | ERR_TX_TOO_LARGE)
+}
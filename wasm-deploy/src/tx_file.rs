@@ -0,0 +1,903 @@
+use std::{
+    collections::BTreeMap,
+    fs::create_dir_all,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use cosm_utils::chain::{error::ChainError, msg::Msg};
+use cosmrs::proto::{
+    cosmos::{
+        authz::v1beta1::{GenericAuthorization, Grant, MsgExec, MsgGrant},
+        base::v1beta1::Coin as ProtoCoin,
+        crypto::{
+            multisig::{v1beta1::CompactBitArray, LegacyAminoPubKey},
+            secp256k1::PubKey,
+        },
+        tx::{
+            signing::v1beta1::SignMode,
+            v1beta1::{mode_info, AuthInfo, Fee, ModeInfo, SignerInfo, TxBody, TxRaw},
+        },
+    },
+    cosmwasm::wasm::v1::{
+        AccessConfig, AccessType, MsgClearAdmin, MsgExecuteContract, MsgInstantiateContract,
+        MsgMigrateContract, MsgStoreCode, MsgUpdateAdmin,
+    },
+    traits::TypeUrl,
+    Any,
+};
+use cosmrs::tendermint::Time;
+use prost::Message;
+use prost_types::Timestamp;
+use serde::{ser::SerializeMap, ser::SerializeSeq, Serialize};
+use serde_json::{json, value::RawValue, Value};
+
+use crate::{
+    authz::{GENERIC_AUTHORIZATION, MSG_EXEC, MSG_GRANT},
+    error::DeployError,
+    instantiate2::MsgInstantiateContract2,
+    permissions::MsgUpdateInstantiateConfig,
+    proposal::{MsgSubmitProposal, MSG_SUBMIT_PROPOSAL},
+    settings::WorkspaceSettings,
+};
+
+const SECP256K1_PUBKEY: &str = "/cosmos.crypto.secp256k1.PubKey";
+const MULTISIG_PUBKEY: &str = "/cosmos.crypto.multisig.LegacyAminoPubKey";
+
+/// The raw json of contract msgs by their json pointer in a tx file.
+/// Contract msgs are signed as the exact bytes of their json, so they are never reformatted.
+type RawMsgs = BTreeMap<String, Box<RawValue>>;
+
+/// A tx in the json format of the Cosmos SDK, as written by `--generate-only`
+/// and read by `tx sign` and `tx broadcast`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TxFile {
+    pub body: TxBody,
+    pub auth_info: AuthInfo,
+    pub signatures: Vec<Vec<u8>>,
+}
+
+impl TxFile {
+    /// A tx without signer infos or signatures, to be signed elsewhere.
+    pub fn unsigned(msgs: Vec<Any>, memo: String, timeout_height: u64, fee: Fee) -> TxFile {
+        TxFile {
+            body: TxBody {
+                messages: msgs,
+                memo,
+                timeout_height,
+                ..Default::default()
+            },
+            auth_info: AuthInfo {
+                signer_infos: vec![],
+                fee: Some(fee),
+                tip: None,
+            },
+            signatures: vec![],
+        }
+    }
+
+    pub fn from_raw(raw: &TxRaw) -> anyhow::Result<TxFile> {
+        Ok(TxFile {
+            body: TxBody::decode(raw.body_bytes.as_slice())?,
+            auth_info: AuthInfo::decode(raw.auth_info_bytes.as_slice())?,
+            signatures: raw.signatures.clone(),
+        })
+    }
+
+    pub fn to_raw(&self) -> TxRaw {
+        TxRaw {
+            body_bytes: self.body.encode_to_vec(),
+            auth_info_bytes: self.auth_info.encode_to_vec(),
+            signatures: self.signatures.clone(),
+        }
+    }
+
+    /// The fee of the tx, to be signed again.
+    pub fn fee(&self) -> anyhow::Result<cosm_utils::chain::fee::Fee> {
+        let fee = self.auth_info.fee.clone().unwrap_or_default();
+        let address = |x: String| -> anyhow::Result<_> {
+            match x.is_empty() {
+                true => Ok(None),
+                false => Ok(Some(x.parse()?)),
+            }
+        };
+        Ok(cosm_utils::chain::fee::Fee {
+            amount: fee
+                .amount
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<Vec<_>, _>>()?,
+            gas_limit: fee.gas_limit.into(),
+            payer: address(fee.payer)?,
+            granter: address(fee.granter)?,
+        })
+    }
+
+    /// The addresses that must sign the tx, which are the signers of its msgs and the fee payer.
+    pub fn signers(&self) -> anyhow::Result<Vec<String>> {
+        let mut signers: Vec<String> = vec![];
+        for any in &self.body.messages {
+            let msg = msg_to_json(any)?;
+            let signer_field = match any.type_url.as_str() {
+                MSG_GRANT => "granter",
+                MSG_EXEC => "grantee",
+                MSG_SUBMIT_PROPOSAL => "proposer",
+                _ => "sender",
+            };
+            let signer = msg.get(signer_field).and_then(Value::as_str).ok_or(
+                DeployError::InvalidTxFile {
+                    reason: format!("no {signer_field} found in {}", any.type_url),
+                },
+            )?;
+            if !signers.iter().any(|x| x == signer) {
+                signers.push(signer.to_string());
+            }
+        }
+        let payer = self.auth_info.fee.as_ref().map(|x| x.payer.as_str());
+        if let Some(payer) = payer.filter(|x| !x.is_empty()) {
+            if !signers.iter().any(|x| x == payer) {
+                signers.push(payer.to_string());
+            }
+        }
+        Ok(signers)
+    }
+
+    /// The msgs of the tx, to be signed again.
+    pub fn msgs(&self) -> Vec<EncodedMsg> {
+        self.body
+            .messages
+            .iter()
+            .map(|x| EncodedMsg(x.clone()))
+            .collect()
+    }
+
+    pub fn to_json(&self) -> anyhow::Result<Value> {
+        self.encode_json(&mut RawMsgs::new())
+    }
+
+    /// The json text of the tx, with contract msgs written exactly as they are signed.
+    pub fn to_json_string(&self) -> anyhow::Result<String> {
+        let mut raw = RawMsgs::new();
+        let value = self.encode_json(&mut raw)?;
+        Ok(serde_json::to_string_pretty(&WithRawMsgs {
+            value: &value,
+            pointer: String::new(),
+            raw: &raw,
+        })?)
+    }
+
+    fn encode_json(&self, raw: &mut RawMsgs) -> anyhow::Result<Value> {
+        let fee = self.auth_info.fee.clone().unwrap_or_default();
+        Ok(json!({
+            "body": {
+                "messages": self
+                    .body
+                    .messages
+                    .iter()
+                    .enumerate()
+                    .map(|(i, x)| encode_msg(x, &format!("/body/messages/{i}"), raw))
+                    .collect::<anyhow::Result<Vec<_>>>()?,
+                "memo": self.body.memo,
+                "timeout_height": self.body.timeout_height.to_string(),
+                "extension_options": [],
+                "non_critical_extension_options": [],
+            },
+            "auth_info": {
+                "signer_infos": self
+                    .auth_info
+                    .signer_infos
+                    .iter()
+                    .map(signer_info_to_json)
+                    .collect::<anyhow::Result<Vec<_>>>()?,
+                "fee": {
+                    "amount": coins_to_json(&fee.amount),
+                    "gas_limit": fee.gas_limit.to_string(),
+                    "payer": fee.payer,
+                    "granter": fee.granter,
+                },
+                "tip": null,
+            },
+            "signatures": self.signatures.iter().map(|x| STANDARD.encode(x)).collect::<Vec<_>>(),
+        }))
+    }
+
+    pub fn from_json(value: &Value) -> anyhow::Result<TxFile> {
+        TxFile::decode_json(value, &RawMsgs::new())
+    }
+
+    /// Reads the json text of a tx, keeping contract msgs exactly as they were signed.
+    pub fn from_json_str(text: &str) -> anyhow::Result<TxFile> {
+        let mut raw = RawMsgs::new();
+        collect_raw_msgs(serde_json::from_str(text)?, String::new(), &mut raw)?;
+        TxFile::decode_json(&serde_json::from_str(text)?, &raw)
+    }
+
+    fn decode_json(value: &Value, raw: &RawMsgs) -> anyhow::Result<TxFile> {
+        let body = field(value, "body");
+        let auth_info = field(value, "auth_info");
+        let fee = field(auth_info, "fee");
+        Ok(TxFile {
+            body: TxBody {
+                messages: array(body, "messages")?
+                    .iter()
+                    .enumerate()
+                    .map(|(i, x)| decode_msg(x, &format!("/body/messages/{i}"), raw))
+                    .collect::<anyhow::Result<Vec<_>>>()?,
+                memo: string(body, "memo")?,
+                timeout_height: uint64(body, "timeout_height")?,
+                ..Default::default()
+            },
+            auth_info: AuthInfo {
+                signer_infos: array(auth_info, "signer_infos")?
+                    .iter()
+                    .map(signer_info_from_json)
+                    .collect::<anyhow::Result<Vec<_>>>()?,
+                fee: Some(Fee {
+                    amount: coins(fee, "amount")?,
+                    gas_limit: uint64(fee, "gas_limit")?,
+                    payer: string(fee, "payer")?,
+                    granter: string(fee, "granter")?,
+                }),
+                tip: None,
+            },
+            signatures: array(value, "signatures")?
+                .iter()
+                .map(|x| decode_base64(x.as_str().unwrap_or_default()))
+                .collect::<anyhow::Result<Vec<_>>>()?,
+        })
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<TxFile> {
+        TxFile::from_json_str(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)?;
+        }
+        std::fs::write(path, self.to_json_string()?)?;
+        Ok(())
+    }
+
+    /// Default path is `.wasm-deploy/txs/<env_id>/<timestamp>.json`
+    pub fn generated_path(settings: &WorkspaceSettings, env_id: &str) -> anyhow::Result<PathBuf> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+        Ok(settings
+            .config_path
+            .parent()
+            .expect("Invalid CONFIG_PATH")
+            .join("txs")
+            .join(env_id)
+            .join(format!("{timestamp}.json")))
+    }
+}
+
+/// A msg that is already encoded, such as one read from a tx file.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct EncodedMsg(#[serde(serialize_with = "serialize_any")] pub Any);
+
+fn serialize_any<S: serde::Serializer>(any: &Any, serializer: S) -> Result<S::Ok, S::Error> {
+    msg_to_json(any)
+        .map_err(serde::ser::Error::custom)?
+        .serialize(serializer)
+}
+
+/// The wire format of `Any`, so that an `EncodedMsg` can be handed to cosm-utils.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct EncodedAny {
+    #[prost(string, tag = "1")]
+    pub type_url: String,
+    #[prost(bytes = "vec", tag = "2")]
+    pub value: Vec<u8>,
+}
+
+impl TypeUrl for EncodedAny {
+    // Never used, encoded msgs keep the type url they were read with
+    const TYPE_URL: &'static str = "";
+}
+
+impl Msg for EncodedMsg {
+    type Proto = EncodedAny;
+    type Err = ChainError;
+
+    fn into_any(self) -> Result<Any, Self::Err> {
+        Ok(self.0)
+    }
+}
+
+impl TryFrom<EncodedAny> for EncodedMsg {
+    type Error = ChainError;
+
+    fn try_from(any: EncodedAny) -> Result<Self, Self::Error> {
+        Ok(EncodedMsg(Any {
+            type_url: any.type_url,
+            value: any.value,
+        }))
+    }
+}
+
+impl TryFrom<EncodedMsg> for EncodedAny {
+    type Error = ChainError;
+
+    fn try_from(msg: EncodedMsg) -> Result<Self, Self::Error> {
+        Ok(EncodedAny {
+            type_url: msg.0.type_url,
+            value: msg.0.value,
+        })
+    }
+}
+
+/// Converts a msg to the json of the Cosmos SDK, for the msg types that wasm-deploy sends.
+pub fn msg_to_json(any: &Any) -> anyhow::Result<Value> {
+    encode_msg(any, "", &mut RawMsgs::new())
+}
+
+/// Converts a msg at `pointer` of a tx file to json, adding the raw json of its contract msg to `raw`.
+fn encode_msg(any: &Any, pointer: &str, raw: &mut RawMsgs) -> anyhow::Result<Value> {
+    let value = any.value.as_slice();
+    let mut msg = match any.type_url.as_str() {
+        MsgStoreCode::TYPE_URL => {
+            let msg = MsgStoreCode::decode(value)?;
+            json!({
+                "sender": msg.sender,
+                "wasm_byte_code": STANDARD.encode(msg.wasm_byte_code),
+                "instantiate_permission": msg.instantiate_permission.as_ref().map(access_config_to_json),
+            })
+        }
+        MsgInstantiateContract::TYPE_URL => {
+            let msg = MsgInstantiateContract::decode(value)?;
+            json!({
+                "sender": msg.sender,
+                "admin": msg.admin,
+                "code_id": msg.code_id.to_string(),
+                "label": msg.label,
+                "msg": contract_msg_to_json(msg.msg, pointer, raw)?,
+                "funds": coins_to_json(&msg.funds),
+            })
+        }
+        MsgInstantiateContract2::TYPE_URL => {
+            let msg = MsgInstantiateContract2::decode(value)?;
+            json!({
+                "sender": msg.sender,
+                "admin": msg.admin,
+                "code_id": msg.code_id.to_string(),
+                "label": msg.label,
+                "msg": contract_msg_to_json(msg.msg, pointer, raw)?,
+                "funds": coins_to_json(&msg.funds),
+                "salt": STANDARD.encode(msg.salt),
+                "fix_msg": msg.fix_msg,
+            })
+        }
+        MsgExecuteContract::TYPE_URL => {
+            let msg = MsgExecuteContract::decode(value)?;
+            json!({
+                "sender": msg.sender,
+                "contract": msg.contract,
+                "msg": contract_msg_to_json(msg.msg, pointer, raw)?,
+                "funds": coins_to_json(&msg.funds),
+            })
+        }
+        MsgMigrateContract::TYPE_URL => {
+            let msg = MsgMigrateContract::decode(value)?;
+            json!({
+                "sender": msg.sender,
+                "contract": msg.contract,
+                "code_id": msg.code_id.to_string(),
+                "msg": contract_msg_to_json(msg.msg, pointer, raw)?,
+            })
+        }
+        MsgUpdateAdmin::TYPE_URL => {
+            let msg = MsgUpdateAdmin::decode(value)?;
+            json!({
+                "sender": msg.sender,
+                "new_admin": msg.new_admin,
+                "contract": msg.contract,
+            })
+        }
+        MsgClearAdmin::TYPE_URL => {
+            let msg = MsgClearAdmin::decode(value)?;
+            json!({
+                "sender": msg.sender,
+                "contract": msg.contract,
+            })
+        }
+        MsgUpdateInstantiateConfig::TYPE_URL => {
+            let msg = MsgUpdateInstantiateConfig::decode(value)?;
+            json!({
+                "sender": msg.sender,
+                "code_id": msg.code_id.to_string(),
+                "new_instantiate_permission": msg.new_instantiate_permission.as_ref().map(access_config_to_json),
+            })
+        }
+        MSG_EXEC => {
+            let msg = MsgExec::decode(value)?;
+            json!({
+                "grantee": msg.grantee,
+                "msgs": msg
+                    .msgs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, x)| encode_msg(x, &format!("{pointer}/msgs/{i}"), raw))
+                    .collect::<anyhow::Result<Vec<_>>>()?,
+            })
+        }
+        MSG_GRANT => {
+            let msg = MsgGrant::decode(value)?;
+            let grant = msg.grant.unwrap_or_default();
+            let authorization = grant.authorization.unwrap_or_default();
+            if authorization.type_url != GENERIC_AUTHORIZATION {
+                return Err(DeployError::UnsupportedMsgType {
+                    type_url: authorization.type_url,
+                }
+                .into());
+            }
+            json!({
+                "granter": msg.granter,
+                "grantee": msg.grantee,
+                "grant": {
+                    "authorization": {
+                        "@type": authorization.type_url,
+                        "msg": GenericAuthorization::decode(authorization.value.as_slice())?.msg,
+                    },
+                    "expiration": grant
+                        .expiration
+                        .map(|x| Time::from_unix_timestamp(x.seconds, x.nanos as u32))
+                        .transpose()?
+                        .map(|x| x.to_rfc3339()),
+                },
+            })
+        }
+        MSG_SUBMIT_PROPOSAL => {
+            let msg = MsgSubmitProposal::decode(value)?;
+            json!({
+                "messages": msg
+                    .messages
+                    .iter()
+                    .enumerate()
+                    .map(|(i, x)| encode_msg(x, &format!("{pointer}/messages/{i}"), raw))
+                    .collect::<anyhow::Result<Vec<_>>>()?,
+                "initial_deposit": coins_to_json(&msg.initial_deposit),
+                "proposer": msg.proposer,
+                "metadata": msg.metadata,
+                "title": msg.title,
+                "summary": msg.summary,
+            })
+        }
+        type_url => {
+            return Err(DeployError::UnsupportedMsgType {
+                type_url: type_url.to_string(),
+            }
+            .into())
+        }
+    };
+    msg["@type"] = any.type_url.clone().into();
+    Ok(msg)
+}
+
+/// Reads a msg from the json of the Cosmos SDK, for the msg types that wasm-deploy sends.
+pub fn msg_from_json(msg: &Value) -> anyhow::Result<Any> {
+    decode_msg(msg, "", &RawMsgs::new())
+}
+
+/// Reads a msg at `pointer` of a tx file, taking its contract msg from `raw` when it is there.
+fn decode_msg(msg: &Value, pointer: &str, raw: &RawMsgs) -> anyhow::Result<Any> {
+    let type_url = string(msg, "@type")?;
+    let value = match type_url.as_str() {
+        MsgStoreCode::TYPE_URL => MsgStoreCode {
+            sender: string(msg, "sender")?,
+            wasm_byte_code: bytes(msg, "wasm_byte_code")?,
+            instantiate_permission: access_config(msg, "instantiate_permission")?,
+        }
+        .encode_to_vec(),
+        MsgInstantiateContract::TYPE_URL => MsgInstantiateContract {
+            sender: string(msg, "sender")?,
+            admin: string(msg, "admin")?,
+            code_id: uint64(msg, "code_id")?,
+            label: string(msg, "label")?,
+            msg: contract_msg(msg, pointer, raw)?,
+            funds: coins(msg, "funds")?,
+        }
+        .encode_to_vec(),
+        MsgInstantiateContract2::TYPE_URL => MsgInstantiateContract2 {
+            sender: string(msg, "sender")?,
+            admin: string(msg, "admin")?,
+            code_id: uint64(msg, "code_id")?,
+            label: string(msg, "label")?,
+            msg: contract_msg(msg, pointer, raw)?,
+            funds: coins(msg, "funds")?,
+            salt: bytes(msg, "salt")?,
+            fix_msg: field(msg, "fix_msg").as_bool().unwrap_or_default(),
+        }
+        .encode_to_vec(),
+        MsgExecuteContract::TYPE_URL => MsgExecuteContract {
+            sender: string(msg, "sender")?,
+            contract: string(msg, "contract")?,
+            msg: contract_msg(msg, pointer, raw)?,
+            funds: coins(msg, "funds")?,
+        }
+        .encode_to_vec(),
+        MsgMigrateContract::TYPE_URL => MsgMigrateContract {
+            sender: string(msg, "sender")?,
+            contract: string(msg, "contract")?,
+            code_id: uint64(msg, "code_id")?,
+            msg: contract_msg(msg, pointer, raw)?,
+        }
+        .encode_to_vec(),
+        MsgUpdateAdmin::TYPE_URL => MsgUpdateAdmin {
+            sender: string(msg, "sender")?,
+            new_admin: string(msg, "new_admin")?,
+            contract: string(msg, "contract")?,
+        }
+        .encode_to_vec(),
+        MsgClearAdmin::TYPE_URL => MsgClearAdmin {
+            sender: string(msg, "sender")?,
+            contract: string(msg, "contract")?,
+        }
+        .encode_to_vec(),
+        MsgUpdateInstantiateConfig::TYPE_URL => MsgUpdateInstantiateConfig {
+            sender: string(msg, "sender")?,
+            code_id: uint64(msg, "code_id")?,
+            new_instantiate_permission: access_config(msg, "new_instantiate_permission")?,
+        }
+        .encode_to_vec(),
+        MSG_EXEC => MsgExec {
+            grantee: string(msg, "grantee")?,
+            msgs: array(msg, "msgs")?
+                .iter()
+                .enumerate()
+                .map(|(i, x)| decode_msg(x, &format!("{pointer}/msgs/{i}"), raw))
+                .collect::<anyhow::Result<Vec<_>>>()?,
+        }
+        .encode_to_vec(),
+        MSG_GRANT => {
+            let grant = field(msg, "grant");
+            let authorization = field(grant, "authorization");
+            let authorization_type = string(authorization, "@type")?;
+            if authorization_type != GENERIC_AUTHORIZATION {
+                return Err(DeployError::UnsupportedMsgType {
+                    type_url: authorization_type,
+                }
+                .into());
+            }
+            let expiration = match field(grant, "expiration") {
+                Value::Null => None,
+                _ => {
+                    let time = Time::parse_from_rfc3339(&string(grant, "expiration")?)?;
+                    Some(Timestamp {
+                        seconds: time.unix_timestamp(),
+                        nanos: 0,
+                    })
+                }
+            };
+            MsgGrant {
+                granter: string(msg, "granter")?,
+                grantee: string(msg, "grantee")?,
+                grant: Some(Grant {
+                    authorization: Some(Any {
+                        type_url: authorization_type,
+                        value: GenericAuthorization {
+                            msg: string(authorization, "msg")?,
+                        }
+                        .encode_to_vec(),
+                    }),
+                    expiration,
+                }),
+            }
+            .encode_to_vec()
+        }
+        MSG_SUBMIT_PROPOSAL => MsgSubmitProposal {
+            messages: array(msg, "messages")?
+                .iter()
+                .enumerate()
+                .map(|(i, x)| decode_msg(x, &format!("{pointer}/messages/{i}"), raw))
+                .collect::<anyhow::Result<Vec<_>>>()?,
+            initial_deposit: coins(msg, "initial_deposit")?,
+            proposer: string(msg, "proposer")?,
+            metadata: string(msg, "metadata")?,
+            title: string(msg, "title")?,
+            summary: string(msg, "summary")?,
+        }
+        .encode_to_vec(),
+        _ => return Err(DeployError::UnsupportedMsgType { type_url }.into()),
+    };
+    Ok(Any { type_url, value })
+}
+
+fn contract_msg_to_json(msg: Vec<u8>, pointer: &str, raw: &mut RawMsgs) -> anyhow::Result<Value> {
+    let msg = RawValue::from_string(String::from_utf8(msg)?)?;
+    let value = serde_json::from_str(msg.get())?;
+    raw.insert(format!("{pointer}/msg"), msg);
+    Ok(value)
+}
+
+fn contract_msg(msg: &Value, pointer: &str, raw: &RawMsgs) -> anyhow::Result<Vec<u8>> {
+    match raw.get(&format!("{pointer}/msg")) {
+        Some(raw) => Ok(raw.get().as_bytes().to_vec()),
+        None => Ok(serde_json::to_vec(field(msg, "msg"))?),
+    }
+}
+
+/// Collects the raw json of every `msg` field, by its json pointer.
+fn collect_raw_msgs(value: &RawValue, pointer: String, raw: &mut RawMsgs) -> anyhow::Result<()> {
+    match value.get().as_bytes().first() {
+        Some(b'{') => {
+            for (key, value) in serde_json::from_str::<BTreeMap<String, &RawValue>>(value.get())? {
+                let pointer = format!("{pointer}/{key}");
+                if key == "msg" {
+                    raw.insert(pointer.clone(), value.to_owned());
+                }
+                collect_raw_msgs(value, pointer, raw)?;
+            }
+        }
+        Some(b'[') => {
+            for (i, value) in serde_json::from_str::<Vec<&RawValue>>(value.get())?
+                .into_iter()
+                .enumerate()
+            {
+                collect_raw_msgs(value, format!("{pointer}/{i}"), raw)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Serializes a json value, writing the raw json of `raw` at its pointers instead.
+struct WithRawMsgs<'a> {
+    value: &'a Value,
+    pointer: String,
+    raw: &'a RawMsgs,
+}
+
+impl Serialize for WithRawMsgs<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if let Some(raw) = self.raw.get(&self.pointer) {
+            return raw.serialize(serializer);
+        }
+        let child = |key: &dyn std::fmt::Display, value| WithRawMsgs {
+            value,
+            pointer: format!("{}/{key}", self.pointer),
+            raw: self.raw,
+        };
+        match self.value {
+            Value::Object(map) => {
+                let mut ser = serializer.serialize_map(Some(map.len()))?;
+                for (key, value) in map {
+                    ser.serialize_entry(key, &child(key, value))?;
+                }
+                ser.end()
+            }
+            Value::Array(array) => {
+                let mut ser = serializer.serialize_seq(Some(array.len()))?;
+                for (i, value) in array.iter().enumerate() {
+                    ser.serialize_element(&child(&i, value))?;
+                }
+                ser.end()
+            }
+            value => value.serialize(serializer),
+        }
+    }
+}
+
+fn signer_info_to_json(signer_info: &SignerInfo) -> anyhow::Result<Value> {
+    Ok(json!({
+        "public_key": signer_info.public_key.as_ref().map(public_key_to_json).transpose()?,
+        "mode_info": signer_info.mode_info.as_ref().map(mode_info_to_json),
+        "sequence": signer_info.sequence.to_string(),
+    }))
+}
+
+fn signer_info_from_json(signer_info: &Value) -> anyhow::Result<SignerInfo> {
+    let public_key = field(signer_info, "public_key");
+    let mode_info = field(signer_info, "mode_info");
+    Ok(SignerInfo {
+        public_key: match public_key.is_null() {
+            true => None,
+            false => Some(public_key_from_json(public_key)?),
+        },
+        mode_info: match mode_info.is_null() {
+            true => None,
+            false => Some(mode_info_from_json(mode_info)?),
+        },
+        sequence: uint64(signer_info, "sequence")?,
+    })
+}
+
+fn public_key_to_json(any: &Any) -> anyhow::Result<Value> {
+    match any.type_url.as_str() {
+        SECP256K1_PUBKEY => Ok(json!({
+            "@type": any.type_url,
+            "key": STANDARD.encode(PubKey::decode(any.value.as_slice())?.key),
+        })),
+        MULTISIG_PUBKEY => {
+            let multisig = LegacyAminoPubKey::decode(any.value.as_slice())?;
+            Ok(json!({
+                "@type": any.type_url,
+                "threshold": multisig.threshold,
+                "public_keys": multisig
+                    .public_keys
+                    .iter()
+                    .map(public_key_to_json)
+                    .collect::<anyhow::Result<Vec<_>>>()?,
+            }))
+        }
+        type_url => Err(DeployError::UnsupportedMsgType {
+            type_url: type_url.to_string(),
+        }
+        .into()),
+    }
+}
+
+fn public_key_from_json(public_key: &Value) -> anyhow::Result<Any> {
+    let type_url = string(public_key, "@type")?;
+    let value = match type_url.as_str() {
+        SECP256K1_PUBKEY => PubKey {
+            key: bytes(public_key, "key")?,
+        }
+        .encode_to_vec(),
+        MULTISIG_PUBKEY => LegacyAminoPubKey {
+            threshold: uint64(public_key, "threshold")? as u32,
+            public_keys: array(public_key, "public_keys")?
+                .iter()
+                .map(public_key_from_json)
+                .collect::<anyhow::Result<Vec<_>>>()?,
+        }
+        .encode_to_vec(),
+        _ => return Err(DeployError::UnsupportedMsgType { type_url }.into()),
+    };
+    Ok(Any { type_url, value })
+}
+
+fn mode_info_to_json(mode_info: &ModeInfo) -> Value {
+    match &mode_info.sum {
+        Some(mode_info::Sum::Single(single)) => json!({
+            "single": { "mode": sign_mode_name(single.mode) }
+        }),
+        Some(mode_info::Sum::Multi(multi)) => json!({
+            "multi": {
+                "bitarray": multi.bitarray.as_ref().map(|bitarray| json!({
+                    "extra_bits_stored": bitarray.extra_bits_stored,
+                    "elems": STANDARD.encode(&bitarray.elems),
+                })),
+                "mode_infos": multi.mode_infos.iter().map(mode_info_to_json).collect::<Vec<_>>(),
+            }
+        }),
+        None => Value::Null,
+    }
+}
+
+fn mode_info_from_json(mode_info: &Value) -> anyhow::Result<ModeInfo> {
+    let single = field(mode_info, "single");
+    let multi = field(mode_info, "multi");
+    let sum = if !single.is_null() {
+        let name = string(single, "mode")?;
+        let mode =
+            (0..=5)
+                .find(|x| sign_mode_name(*x) == name)
+                .ok_or(DeployError::InvalidTxFile {
+                    reason: format!("unknown sign mode {name}"),
+                })?;
+        Some(mode_info::Sum::Single(mode_info::Single { mode }))
+    } else if !multi.is_null() {
+        let bitarray = field(multi, "bitarray");
+        Some(mode_info::Sum::Multi(mode_info::Multi {
+            bitarray: match bitarray.is_null() {
+                true => None,
+                false => Some(CompactBitArray {
+                    extra_bits_stored: uint64(bitarray, "extra_bits_stored")? as u32,
+                    elems: bytes(bitarray, "elems")?,
+                }),
+            },
+            mode_infos: array(multi, "mode_infos")?
+                .iter()
+                .map(mode_info_from_json)
+                .collect::<anyhow::Result<Vec<_>>>()?,
+        }))
+    } else {
+        None
+    };
+    Ok(ModeInfo { sum })
+}
+
+fn sign_mode_name(mode: i32) -> &'static str {
+    SignMode::from_i32(mode)
+        .unwrap_or(SignMode::Unspecified)
+        .as_str_name()
+}
+
+fn access_config_to_json(access_config: &AccessConfig) -> Value {
+    json!({
+        "permission": AccessType::from_i32(access_config.permission)
+            .unwrap_or(AccessType::Unspecified)
+            .as_str_name(),
+        "addresses": access_config.addresses,
+    })
+}
+
+fn access_config(value: &Value, name: &str) -> anyhow::Result<Option<AccessConfig>> {
+    let access_config = field(value, name);
+    if access_config.is_null() {
+        return Ok(None);
+    }
+    let permission = string(access_config, "permission")?;
+    let permission = (0..=4)
+        .filter_map(AccessType::from_i32)
+        .find(|x| x.as_str_name() == permission)
+        .ok_or(DeployError::InvalidTxFile {
+            reason: format!("unknown access type {permission}"),
+        })?;
+    Ok(Some(AccessConfig {
+        permission: permission as i32,
+        address: String::new(),
+        addresses: array(access_config, "addresses")?
+            .iter()
+            .map(|x| x.as_str().unwrap_or_default().to_string())
+            .collect(),
+    }))
+}
+
+fn coins_to_json(coins: &[ProtoCoin]) -> Value {
+    coins
+        .iter()
+        .map(|x| json!({ "denom": x.denom, "amount": x.amount }))
+        .collect()
+}
+
+fn coins(value: &Value, name: &str) -> anyhow::Result<Vec<ProtoCoin>> {
+    array(value, name)?
+        .iter()
+        .map(|coin| {
+            Ok(ProtoCoin {
+                denom: string(coin, "denom")?,
+                amount: string(coin, "amount")?,
+            })
+        })
+        .collect()
+}
+
+/// The field `name` of `value`, which is null when missing, like proto3 default values.
+fn field<'a>(value: &'a Value, name: &str) -> &'a Value {
+    value.get(name).unwrap_or(&Value::Null)
+}
+
+fn string(value: &Value, name: &str) -> anyhow::Result<String> {
+    match field(value, name) {
+        Value::Null => Ok(String::new()),
+        Value::String(string) => Ok(string.clone()),
+        _ => Err(invalid_field(name)),
+    }
+}
+
+/// 64 bit integers are strings in the json of the Cosmos SDK, smaller ones are numbers.
+fn uint64(value: &Value, name: &str) -> anyhow::Result<u64> {
+    match field(value, name) {
+        Value::Null => Ok(0),
+        Value::String(string) => string.parse().map_err(|_| invalid_field(name)),
+        Value::Number(number) => number.as_u64().ok_or_else(|| invalid_field(name)),
+        _ => Err(invalid_field(name)),
+    }
+}
+
+fn bytes(value: &Value, name: &str) -> anyhow::Result<Vec<u8>> {
+    decode_base64(&string(value, name)?).map_err(|_| invalid_field(name))
+}
+
+fn array<'a>(value: &'a Value, name: &str) -> anyhow::Result<&'a [Value]> {
+    match field(value, name) {
+        Value::Null => Ok(&[]),
+        Value::Array(array) => Ok(array),
+        _ => Err(invalid_field(name)),
+    }
+}
+
+fn decode_base64(string: &str) -> anyhow::Result<Vec<u8>> {
+    Ok(STANDARD.decode(string)?)
+}
+
+fn invalid_field(name: &str) -> anyhow::Error {
+    DeployError::InvalidTxFile {
+        reason: format!("invalid field {name}"),
+    }
+    .into()
+}
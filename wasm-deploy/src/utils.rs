@@ -1,8 +1,9 @@
-use std::sync::Arc;
+use std::{path::Path, sync::Arc};
 
 use crate::{
+    checksum::git_revision,
     error::DeployError,
-    file::{ContractInfo, CONFIG, WORKSPACE_SETTINGS},
+    file::{ContractInfo, Env, CONFIG, WORKSPACE_SETTINGS},
     settings::WorkspaceSettings,
 };
 use colored::Colorize;
@@ -22,12 +23,20 @@ lazy_static! {
         .unwrap();
 }
 
+/// Replaces strings of the form `&contract` with the address of the contract
+/// and `&contract:port` with its IBC port id, `wasm.<addr>`.
+/// Contracts on every chain of the env can be referenced.
 pub fn replace_strings(value: &mut Value, contracts: &Vec<ContractInfo>) -> anyhow::Result<()> {
     match value {
         Value::String(string) => {
             if let Some((_, new)) = string.split_once('&') {
-                if let Some(contract) = contracts.iter().find(|x| x.name == new) {
+                let (name, port) = match new.strip_suffix(":port") {
+                    Some(name) => (name, true),
+                    None => (new, false),
+                };
+                if let Some(contract) = contracts.iter().find(|x| x.name == name) {
                     match &contract.addr {
+                        Some(addr) if port => *string = format!("wasm.{addr}"),
                         Some(addr) => *string = addr.clone(),
                         None => {
                             return Err(DeployError::AddrNotFound {
@@ -54,6 +63,65 @@ pub fn replace_strings(value: &mut Value, contracts: &Vec<ContractInfo>) -> anyh
     Ok(())
 }
 
+/// Fills in the placeholders of a label template.
+pub fn render_label(
+    template: &str,
+    env_id: &str,
+    contract: &str,
+    version: Option<&str>,
+    git_sha: Option<&str>,
+) -> String {
+    template
+        .replace("{env_id}", env_id)
+        .replace("{contract}", contract)
+        .replace("{version}", version.unwrap_or("unknown"))
+        .replace("{git_sha}", git_sha.unwrap_or("unknown"))
+}
+
+/// Returns the label a contract is instantiated with in the given env.
+/// `path` is the path of the contract crate, if it is part of the workspace.
+pub fn contract_label(
+    settings: &WorkspaceSettings,
+    env: &Env,
+    contract: &str,
+    path: Option<&Path>,
+) -> String {
+    match &env.label_template {
+        Some(template) => {
+            let version = path.and_then(|path| contract_version(settings, path));
+            let git_sha = git_revision(settings)
+                .0
+                .map(|commit| commit.chars().take(7).collect::<String>());
+            render_label(
+                template,
+                &env.env_id,
+                contract,
+                version.as_deref(),
+                git_sha.as_deref(),
+            )
+        }
+        None => contract.to_string(),
+    }
+}
+
+/// Reads the package version from the Cargo.toml of a contract,
+/// falling back to the workspace version when it is inherited.
+fn contract_version(settings: &WorkspaceSettings, path: &Path) -> Option<String> {
+    let read_toml = |path: &Path| -> Option<toml::Value> {
+        toml::from_str(&std::fs::read_to_string(path.join("Cargo.toml")).ok()?).ok()
+    };
+    let manifest = read_toml(&settings.workspace_root.join(path))?;
+    match manifest.get("package")?.get("version")? {
+        toml::Value::String(version) => Some(version.clone()),
+        _ => read_toml(&settings.workspace_root)?
+            .get("workspace")?
+            .get("package")?
+            .get("version")?
+            .as_str()
+            .map(str::to_string),
+    }
+}
+
 /// TODO: perhaps do this differently
 pub fn replace_strings_any<T: Serialize + DeserializeOwned + Clone>(
     object: &mut T,
@@ -72,6 +140,8 @@ pub async fn get_settings() -> anyhow::Result<Arc<WorkspaceSettings>> {
     }
 }
 
+/// Blocks on the config lock, prefer the `DeployContext` handed to the msg builders of `Deploy`.
+#[deprecated(note = "use `DeployContext::code_id` instead")]
 pub fn get_code_id(contract_name: &str) -> anyhow::Result<u64> {
     let config = block_on(CONFIG.read());
     Ok(config
@@ -80,6 +150,8 @@ pub fn get_code_id(contract_name: &str) -> anyhow::Result<u64> {
         .ok_or(DeployError::CodeIdNotFound)?)
 }
 
+/// Blocks on the config lock, prefer the `DeployContext` handed to the msg builders of `Deploy`.
+#[deprecated(note = "use `DeployContext::addr` instead")]
 pub fn get_addr(contract_name: &str) -> anyhow::Result<String> {
     let config = block_on(CONFIG.read());
     Ok(config
@@ -0,0 +1,131 @@
+use colored::Colorize;
+use colored_json::to_colored_json_auto;
+use serde_json::Value;
+
+use crate::{
+    context::DeployContext,
+    contract::{Deploy, Expected},
+    error::DeployError,
+    file::{CHAIN_OVERRIDE, CONFIG},
+    query::query,
+    utils::replace_strings,
+};
+
+/// Whether `actual` satisfies `expected`.
+/// With `subset`, objects only need the expected fields, at any depth.
+pub fn json_matches(expected: &Value, actual: &Value, subset: bool) -> bool {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) if subset => {
+            expected.iter().all(|(key, expected)| {
+                actual
+                    .get(key)
+                    .is_some_and(|actual| json_matches(expected, actual, subset))
+            })
+        }
+        (Value::Array(expected), Value::Array(actual)) if subset => {
+            expected.len() == actual.len()
+                && expected
+                    .iter()
+                    .zip(actual)
+                    .all(|(expected, actual)| json_matches(expected, actual, subset))
+        }
+        _ => expected == actual,
+    }
+}
+
+struct Outcome {
+    contract: String,
+    query: Value,
+    expected: Value,
+    /// The response, or the error if the query failed
+    actual: Result<Value, String>,
+    passed: bool,
+}
+
+/// Runs the verify queries of each contract and prints a pass/fail table.
+/// Returns an error if any query did not return what was expected.
+pub async fn verify(contracts: &[impl Deploy]) -> anyhow::Result<()> {
+    let ctx = DeployContext::load(false, None).await?;
+    let config = CONFIG.read().await;
+    let env_contracts = &config.get_active_env()?.contracts;
+    let mut outcomes = vec![];
+    for contract in contracts {
+        let queries = contract.verify_queries(&ctx);
+        if queries.is_empty() {
+            continue;
+        }
+        println!("Verifying {}", contract.name());
+        let addr = config.get_contract_addr(&contract.to_string())?.clone();
+        for verify_query in queries {
+            let mut msg = serde_json::to_value(&verify_query.msg)?;
+            replace_strings(&mut msg, env_contracts)?;
+            let (mut expected, subset) = match verify_query.expected {
+                Expected::Exact(value) => (value, false),
+                Expected::Subset(value) => (value, true),
+            };
+            replace_strings(&mut expected, env_contracts)?;
+            let chain_id = config.contract_chain_id(&contract.to_string())?;
+            let actual = CHAIN_OVERRIDE
+                .scope(chain_id, query(&config, addr.clone(), &msg))
+                .await
+                .map_err(|e| e.to_string());
+            let passed = actual
+                .as_ref()
+                .is_ok_and(|actual| json_matches(&expected, actual, subset));
+            outcomes.push(Outcome {
+                contract: contract.name(),
+                query: msg,
+                expected,
+                actual,
+                passed,
+            });
+        }
+    }
+
+    if outcomes.is_empty() {
+        return Ok(());
+    }
+    let width = outcomes
+        .iter()
+        .map(|x| x.contract.len())
+        .max()
+        .unwrap_or_default()
+        .max("contract".len());
+    println!(
+        "{}  {}  {}",
+        format!("{:<width$}", "contract").bold(),
+        "result".bold(),
+        "query".bold()
+    );
+    for outcome in &outcomes {
+        let result = if outcome.passed {
+            "pass  ".green()
+        } else {
+            "fail  ".red()
+        };
+        println!(
+            "{:<width$}  {}  {}",
+            outcome.contract, result, outcome.query
+        );
+    }
+
+    let failed = outcomes.iter().filter(|x| !x.passed).collect::<Vec<_>>();
+    for outcome in &failed {
+        println!("{} {}", "Failed:".red(), outcome.contract.bold());
+        println!("query: {}", to_colored_json_auto(&outcome.query)?);
+        println!("expected: {}", to_colored_json_auto(&outcome.expected)?);
+        match &outcome.actual {
+            Ok(actual) => println!("actual: {}", to_colored_json_auto(actual)?),
+            Err(e) => println!("error: {e}"),
+        }
+    }
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(DeployError::VerificationFailed {
+            failed: failed.len(),
+            total: outcomes.len(),
+        }
+        .into())
+    }
+}
@@ -1,3 +1,41 @@
+use std::str::FromStr;
+
+use cosm_utils::modules::auth::model::Address;
+use cosmrs::{
+    crypto::secp256k1::SigningKey,
+    proto::{
+        cosmos::{
+            authz::v1beta1::MsgExec,
+            tx::{
+                signing::v1beta1::SignMode,
+                v1beta1::{mode_info, Fee, ModeInfo, SignDoc, SignerInfo},
+            },
+        },
+        cosmwasm::wasm::v1::{
+            AccessConfig, AccessType, MsgExecuteContract, MsgMigrateContract, MsgStoreCode,
+        },
+        traits::MessageExt,
+    },
+    tendermint::Time,
+};
+use prost::Message;
+use sha2::{Digest, Sha256};
+use wasm_deploy::{
+    authz::{exec_msg, grant_msgs, GRANTED_MSG_TYPES},
+    contract::{ContractInteractive, Deploy, SetUpTarget},
+    deployment::{
+        dependency_layers, deployment_batches, match_instantiate_events, DeploymentStage,
+        InstantiateEvent,
+    },
+    file::ContractInfo,
+    instantiate2::instantiate2_address,
+    proposal::{gov_module_address, proposal_json, stored_checksums, MsgSubmitProposal, Proposal},
+    strum::IntoEnumIterator,
+    tx::{parse_coin, TxConfig},
+    tx_file::{msg_from_json, msg_to_json, TxFile},
+    utils::replace_strings,
+    verify::json_matches,
+};
 use wasm_deploy_derive::contracts;
 
 pub const ADMIN: &str = "I am const";
@@ -33,3 +71,487 @@ fn test() {
         Baz,
     }
 }
+
+#[test]
+fn test_dependency_layers() {
+    #[contracts]
+    pub enum LayeredContracts {
+        #[contract(admin = ADMIN, instantiate = String, depends_on = [Factory])]
+        Vault,
+
+        #[contract(admin = ADMIN, instantiate = String)]
+        Factory,
+
+        #[contract(admin = ADMIN, instantiate = String, depends_on = [Vault, Factory])]
+        Router,
+    }
+
+    impl Deploy for LayeredContracts {}
+
+    let contracts = LayeredContracts::iter().collect::<Vec<_>>();
+    let layers = dependency_layers(&contracts).unwrap();
+    let names = layers
+        .iter()
+        .map(|layer| layer.iter().map(|x| x.name()).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec![vec!["factory"], vec!["vault"], vec!["router"]]);
+
+    // Dependencies outside of the deployed set are assumed to already exist.
+    let layers = dependency_layers(&[LayeredContracts::Router]).unwrap();
+    assert_eq!(layers.len(), 1);
+}
+
+#[test]
+fn test_deployment_batches() {
+    #[contracts]
+    pub enum RoleContracts {
+        #[contract(admin = ADMIN, instantiate = String, depends_on = [Factory])]
+        Vault,
+
+        #[contract(admin = ADMIN, instantiate = String)]
+        Factory,
+    }
+
+    impl Deploy for RoleContracts {
+        fn signer_role(&self, stage: &DeploymentStage) -> String {
+            match self {
+                RoleContracts::Vault => "operator".to_string(),
+                RoleContracts::Factory => stage.default_role().to_string(),
+            }
+        }
+    }
+
+    // The dependency signed for by another key is still instantiated first.
+    let contracts = RoleContracts::iter().collect::<Vec<_>>();
+    let stage = DeploymentStage::Instantiate { interactive: false };
+    let batches = deployment_batches(&contracts, &stage, |role| role.to_string()).unwrap();
+    let roles = batches.iter().map(|(role, _)| role).collect::<Vec<_>>();
+    assert_eq!(roles, vec!["deployer", "operator"]);
+    let names = batches
+        .iter()
+        .map(|(_, group)| group.iter().map(|x| x.name()).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec![vec!["factory"], vec!["vault"]]);
+
+    // Other stages keep the contracts signed for by the same key in one batch.
+    let batches =
+        deployment_batches(&contracts, &DeploymentStage::SetUp, |_| "key".to_string()).unwrap();
+    assert_eq!(batches.len(), 1);
+    assert_eq!(batches[0].1.len(), 2);
+}
+
+#[test]
+fn test_dependency_cycle() {
+    #[contracts]
+    pub enum CyclicContracts {
+        #[contract(admin = ADMIN, instantiate = String, depends_on = [Bar])]
+        Foo,
+
+        #[contract(admin = ADMIN, instantiate = String, depends_on = [Foo])]
+        Bar,
+    }
+
+    impl Deploy for CyclicContracts {}
+
+    let contracts = CyclicContracts::iter().collect::<Vec<_>>();
+    let err = dependency_layers(&contracts).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Dependency cycle detected between contracts: foo, bar"
+    );
+}
+
+#[test]
+fn test_instantiate2_address() {
+    #[contracts]
+    pub enum SaltedContracts {
+        #[contract(admin = ADMIN, instantiate = String, salt = "a")]
+        Salted,
+
+        #[contract(admin = ADMIN, instantiate = String)]
+        Unsalted,
+    }
+
+    assert_eq!(SaltedContracts::Salted.salt(), Some(b"a".to_vec()));
+    assert_eq!(SaltedContracts::Unsalted.salt(), None);
+
+    // Test vector from cosmwasm-std
+    let checksum =
+        hex::decode("13a1fc994cc6d1c81b746ee0c0ff6f90043875e0bf1d9be6b7d779fc978dc2a5").unwrap();
+    let creator = Address::new(
+        "wasm",
+        &hex::decode("9999999999aaaaaaaaaabbbbbbbbbbcccccccccc").unwrap(),
+    )
+    .unwrap();
+    let expected = Address::new(
+        "wasm",
+        &hex::decode("5e865d3e45ad3e961f77fd77d46543417ced44d924dc3e079b5415ff6775f847").unwrap(),
+    )
+    .unwrap();
+    let salt = SaltedContracts::Salted.salt().unwrap();
+    assert_eq!(
+        instantiate2_address(&checksum, &creator, &salt).unwrap(),
+        expected
+    );
+    assert!(instantiate2_address(&checksum, &creator, &[]).is_err());
+}
+
+#[test]
+fn test_tx_config() {
+    let env_defaults = TxConfig {
+        memo: Some("release v1".into()),
+        fee_granter: Some("granter".into()),
+        ..Default::default()
+    };
+    let overrides = TxConfig {
+        memo: Some("hotfix".into()),
+        gas_limit: Some(200_000),
+        ..Default::default()
+    };
+    let merged = env_defaults.merge(&overrides);
+    assert_eq!(merged.memo.as_deref(), Some("hotfix"));
+    assert_eq!(merged.gas_limit, Some(200_000));
+    assert_eq!(merged.fee_granter.as_deref(), Some("granter"));
+
+    // Command line only options are not saved with the env
+    let cli_only = TxConfig {
+        memo: Some("release v1".into()),
+        generate_only: true,
+        from: Some("multisig".into()),
+        ..Default::default()
+    };
+    let serialized = serde_json::to_value(&cli_only).unwrap();
+    assert_eq!(serialized["memo"], "release v1");
+    assert!(serialized.get("generate_only").is_none());
+    assert!(serialized.get("from").is_none());
+    assert!(!cli_only.merge(&overrides).generate_only);
+
+    let coin = parse_coin("5000uosmo").unwrap();
+    assert_eq!(coin.amount, 5000);
+    assert_eq!(coin.denom.to_string(), "uosmo");
+    assert!(parse_coin("uosmo").is_err());
+    assert!(parse_coin("5000").is_err());
+}
+
+#[test]
+fn test_match_instantiate_events() {
+    let event = |msg_index: Option<usize>, address: &str, code_id: u64| InstantiateEvent {
+        msg_index,
+        address: address.to_string(),
+        code_id: Some(code_id),
+    };
+
+    // The first contract instantiates a child with code id 9 before the second msg runs
+    let events = vec![
+        event(None, "a", 1),
+        event(None, "child", 9),
+        event(None, "b", 2),
+    ];
+    let matched = match_instantiate_events(&events, &[1, 2]);
+    assert_eq!(matched[0].as_ref().unwrap().address, "a");
+    assert_eq!(matched[1].as_ref().unwrap().address, "b");
+
+    // Msg indexes take precedence over the order of the events
+    let events = vec![event(Some(1), "b", 1), event(Some(0), "a", 1)];
+    let matched = match_instantiate_events(&events, &[1, 1]);
+    assert_eq!(matched[0].as_ref().unwrap().address, "a");
+    assert_eq!(matched[1].as_ref().unwrap().address, "b");
+
+    // A missing event is reported instead of shifting the addresses
+    let events = vec![event(None, "b", 2)];
+    let matched = match_instantiate_events(&events, &[1, 2]);
+    assert!(matched[0].is_none());
+    assert_eq!(matched[1].as_ref().unwrap().address, "b");
+}
+
+#[test]
+fn test_json_matches() {
+    let actual = serde_json::json!({
+        "owner": "osmo1owner",
+        "config": { "oracle": "osmo1oracle", "fee": "0.01" },
+        "assets": [{ "denom": "uosmo", "weight": 1 }],
+    });
+    let subset = serde_json::json!({
+        "config": { "oracle": "osmo1oracle" },
+        "assets": [{ "denom": "uosmo" }],
+    });
+    assert!(json_matches(&subset, &actual, true));
+    assert!(!json_matches(&subset, &actual, false));
+    assert!(json_matches(&actual, &actual, false));
+    assert!(!json_matches(
+        &serde_json::json!({ "config": { "oracle": "osmo1other" } }),
+        &actual,
+        true
+    ));
+}
+
+#[test]
+fn test_replace_strings_across_chains() {
+    let contracts = vec![
+        ContractInfo {
+            name: "controller".into(),
+            addr: Some("osmo1controller".into()),
+            code_id: Some(1),
+            chain_id: None,
+            provenance: None,
+            code_id_history: vec![],
+        },
+        ContractInfo {
+            name: "host".into(),
+            addr: Some("neutron1host".into()),
+            code_id: Some(2),
+            chain_id: Some("neutron-1".into()),
+            provenance: None,
+            code_id_history: vec![],
+        },
+    ];
+    let mut msg = serde_json::json!({
+        "controller": "&controller",
+        "host": "&host",
+        "host_port": "&host:port",
+        "other": "&unknown",
+    });
+    replace_strings(&mut msg, &contracts).unwrap();
+    assert_eq!(
+        msg,
+        serde_json::json!({
+            "controller": "osmo1controller",
+            "host": "neutron1host",
+            "host_port": "wasm.neutron1host",
+            "other": "&unknown",
+        })
+    );
+}
+
+#[test]
+fn test_set_up_target() {
+    let contracts = vec![
+        ContractInfo {
+            name: "vault".into(),
+            addr: Some("osmo1vault".into()),
+            code_id: Some(1),
+            chain_id: None,
+            provenance: None,
+            code_id_history: vec![],
+        },
+        ContractInfo {
+            name: "factory".into(),
+            addr: None,
+            code_id: Some(2),
+            chain_id: None,
+            provenance: None,
+            code_id_history: vec![],
+        },
+    ];
+    let resolve = |target: SetUpTarget| target.resolve("vault", &contracts);
+    assert_eq!(resolve(SetUpTarget::This).unwrap(), "osmo1vault");
+    assert_eq!(
+        resolve(SetUpTarget::Addr("&vault".into())).unwrap(),
+        "osmo1vault"
+    );
+    assert_eq!(
+        resolve(SetUpTarget::Addr("osmo1external".into())).unwrap(),
+        "osmo1external"
+    );
+    assert!(resolve(SetUpTarget::Contract("factory".into())).is_err());
+    assert!(resolve(SetUpTarget::Addr("&unknown".into())).is_err());
+}
+
+#[test]
+fn test_tx_file() {
+    let store_code = MsgStoreCode {
+        sender: "osmo1multisig".into(),
+        wasm_byte_code: vec![0, 97, 115, 109],
+        instantiate_permission: Some(AccessConfig {
+            permission: AccessType::AnyOfAddresses as i32,
+            address: String::new(),
+            addresses: vec!["osmo1multisig".into()],
+        }),
+    };
+    let migrate = MsgMigrateContract {
+        sender: "osmo1multisig".into(),
+        contract: "osmo1vault".into(),
+        code_id: 42,
+        msg: br#"{"version":"2"}"#.to_vec(),
+    };
+    let tx = TxFile::unsigned(
+        vec![store_code.to_any().unwrap(), migrate.to_any().unwrap()],
+        "upgrade".into(),
+        0,
+        Fee {
+            amount: vec![],
+            gas_limit: 200_000,
+            payer: String::new(),
+            granter: String::new(),
+        },
+    );
+    let json = tx.to_json().unwrap();
+    let messages = &json["body"]["messages"];
+    assert_eq!(messages[0]["@type"], "/cosmwasm.wasm.v1.MsgStoreCode");
+    assert_eq!(messages[0]["wasm_byte_code"], "AGFzbQ==");
+    assert_eq!(
+        messages[0]["instantiate_permission"]["permission"],
+        "ACCESS_TYPE_ANY_OF_ADDRESSES"
+    );
+    assert_eq!(messages[1]["code_id"], "42");
+    assert_eq!(messages[1]["msg"], serde_json::json!({ "version": "2" }));
+    assert_eq!(json["auth_info"]["fee"]["gas_limit"], "200000");
+    assert_eq!(TxFile::from_json(&json).unwrap(), tx);
+    assert_eq!(tx.signers().unwrap(), ["osmo1multisig"]);
+}
+
+#[test]
+fn test_tx_file_raw_msg() {
+    // Signatures cover the exact bytes of contract msgs, so key order and whitespace are kept
+    let msg = r#"{ "withdraw": {"denom" : "uosmo"},  "amount": "1" }"#;
+    let text = r#"{
+        "body": {
+            "messages": [{
+                "@type": "/cosmos.authz.v1beta1.MsgExec",
+                "grantee": "osmo1grantee",
+                "msgs": [{
+                    "@type": "/cosmwasm.wasm.v1.MsgExecuteContract",
+                    "sender": "osmo1granter",
+                    "contract": "osmo1vault",
+                    "msg": MSG,
+                    "funds": []
+                }]
+            }],
+            "memo": "",
+            "timeout_height": "0",
+            "extension_options": [],
+            "non_critical_extension_options": []
+        },
+        "auth_info": {
+            "signer_infos": [],
+            "fee": { "amount": [], "gas_limit": "200000", "payer": "", "granter": "" },
+            "tip": null
+        },
+        "signatures": []
+    }"#
+    .replace("MSG", msg);
+    let tx = TxFile::from_json_str(&text).unwrap();
+    let exec = MsgExec::decode(tx.body.messages[0].value.as_slice()).unwrap();
+    let execute = MsgExecuteContract::decode(exec.msgs[0].value.as_slice()).unwrap();
+    assert_eq!(execute.msg, msg.as_bytes());
+
+    let written = tx.to_json_string().unwrap();
+    assert!(written.contains(msg));
+    assert_eq!(TxFile::from_json_str(&written).unwrap(), tx);
+}
+
+#[test]
+fn test_authz_msgs() {
+    let granter = Address::new("osmo", &[1; 20]).unwrap();
+    let grantee = Address::new("osmo", &[2; 20]).unwrap();
+    let migrate = MsgMigrateContract {
+        sender: granter.to_string(),
+        contract: "osmo1vault".into(),
+        code_id: 42,
+        msg: br#"{}"#.to_vec(),
+    };
+    let exec = exec_msg(&grantee, migrate.to_any().unwrap());
+    let json = msg_to_json(&exec).unwrap();
+    assert_eq!(json["grantee"], grantee.to_string());
+    assert_eq!(
+        json["msgs"][0]["@type"],
+        "/cosmwasm.wasm.v1.MsgMigrateContract"
+    );
+    assert_eq!(msg_from_json(&json).unwrap(), exec);
+
+    let expiration = Time::parse_from_rfc3339("2027-01-01T00:00:00Z").unwrap();
+    let grants = grant_msgs(&granter, &grantee, Some(expiration));
+    assert_eq!(grants.len(), GRANTED_MSG_TYPES.len());
+    let json = msg_to_json(&grants[0]).unwrap();
+    assert_eq!(json["grant"]["authorization"]["msg"], GRANTED_MSG_TYPES[0]);
+    assert_eq!(json["grant"]["expiration"], "2027-01-01T00:00:00Z");
+    assert_eq!(msg_from_json(&json).unwrap(), grants[0]);
+}
+
+#[test]
+fn test_sign_grant_tx_file() {
+    // A grant generated with --from <granter> is signed by the granter
+    let key = SigningKey::from_slice(&[7; 32]).unwrap();
+    let granter = Address::from_str(key.public_key().account_id("osmo").unwrap().as_ref()).unwrap();
+    let grantee = Address::new("osmo", &[2; 20]).unwrap();
+    let mut tx = TxFile::unsigned(
+        grant_msgs(&granter, &grantee, None),
+        String::new(),
+        0,
+        Fee {
+            amount: vec![],
+            gas_limit: 200_000,
+            payer: String::new(),
+            granter: String::new(),
+        },
+    );
+    assert_eq!(tx.signers().unwrap(), [granter.to_string()]);
+    tx.auth_info.signer_infos = vec![SignerInfo {
+        public_key: Some(key.public_key().to_any().unwrap()),
+        mode_info: Some(ModeInfo {
+            sum: Some(mode_info::Sum::Single(mode_info::Single {
+                mode: SignMode::Direct as i32,
+            })),
+        }),
+        sequence: 0,
+    }];
+    let sign_bytes = |tx: &TxFile| {
+        let raw = tx.to_raw();
+        SignDoc {
+            body_bytes: raw.body_bytes,
+            auth_info_bytes: raw.auth_info_bytes,
+            chain_id: "osmosis-1".into(),
+            account_number: 1,
+        }
+        .encode_to_vec()
+    };
+
+    let mut signed = TxFile::from_json_str(&tx.to_json_string().unwrap()).unwrap();
+    let signature = key.sign(&sign_bytes(&signed)).unwrap().to_vec();
+    signed.signatures = vec![signature.clone()];
+    let broadcast = TxFile::from_json_str(&signed.to_json_string().unwrap()).unwrap();
+    assert_eq!(broadcast.signatures, signed.signatures);
+    // Signatures are deterministic, so the same signature means the same sign bytes
+    assert_eq!(
+        key.sign(&sign_bytes(&broadcast)).unwrap().to_vec(),
+        signature
+    );
+}
+
+#[test]
+fn test_proposal() {
+    let authority = gov_module_address("cosmos").unwrap();
+    assert_eq!(
+        authority.to_string(),
+        "cosmos10d07y265gmmuvt4z0w9aw880jnsr700j6zn9kn"
+    );
+    let store_code = MsgStoreCode {
+        sender: authority.to_string(),
+        wasm_byte_code: vec![0, 97, 115, 109],
+        instantiate_permission: None,
+    };
+    let msg = MsgSubmitProposal {
+        messages: vec![store_code.to_any().unwrap()],
+        initial_deposit: vec![],
+        proposer: "cosmos1proposer".into(),
+        metadata: String::new(),
+        title: "store_code vault".into(),
+        summary: "Code checksums:".into(),
+    };
+    let json = proposal_json(&msg).unwrap();
+    assert_eq!(json["messages"][0]["sender"], authority.to_string());
+    assert_eq!(json["title"], "store_code vault");
+
+    let proposal = Proposal {
+        id: 1,
+        messages: msg.messages,
+        status: 3,
+    };
+    assert_eq!(
+        stored_checksums(&proposal).unwrap(),
+        vec![(
+            hex::encode(Sha256::digest([0, 97, 115, 109])),
+            authority.to_string()
+        )]
+    );
+}
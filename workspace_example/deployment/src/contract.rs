@@ -1,5 +1,6 @@
 // This file defines your contract. It's mostly boiler plate.
-use crate::defaults::{ADMIN, CW20_INSTANTIATE, CW20_MINT};
+use crate::defaults::{cw20_instantiate, cw20_mint, ADMIN};
+use wasm_deploy::context::DeployContext;
 use wasm_deploy::contract::{Deploy, Msg};
 use wasm_deploy::derive::contracts;
 
@@ -22,6 +23,8 @@ pub enum Contracts {
         rename = "cw20_base"
         // cw20_send = ...             
         // migrate = ...                
+        // depends_on = [OtherContract] // contracts that must be instantiated first
+        // salt = "my_salt" // instantiates at a predictable address with MsgInstantiateContract2
         // bin_name = "cw20"             // | You should only need to change these
         // path = "contracts/cw20_base"  // | if you have a non-standard workspace layout.
 
@@ -36,25 +39,32 @@ pub enum Contracts {
 // Generally you'll want to match on the Contracts enum and handle the logic for each contract.
 impl Deploy for Contracts {
     // This method gets the preprogrammed instantiate msg for the contract.
-    fn instantiate_msg(&self) -> Option<Box<dyn Msg>> {
+    fn instantiate_msg(&self, _ctx: &DeployContext) -> Option<Box<dyn Msg>> {
         match self {
-            Contracts::Cw20Base { .. } => Some(Box::new(CW20_INSTANTIATE.to_owned())),
+            Contracts::Cw20Base { .. } => Some(Box::new(cw20_instantiate())),
         }
     }
 
     // This method gets the preprogrammed migrate msg for the contract.
-    fn migrate_msg(&self) -> Option<Box<dyn Msg>> {
+    fn migrate_msg(&self, _ctx: &DeployContext) -> Option<Box<dyn Msg>> {
         match self {
-            Contracts::Cw20Base { .. } => Some(Box::new(CW20_INSTANTIATE.to_owned())),
+            Contracts::Cw20Base { .. } => Some(Box::new(cw20_instantiate())),
+        }
+    }
+
+    // This method gets the preprogrammed migrate msg used to roll the contract back to `code_id`.
+    fn rollback_msg(&self, _ctx: &DeployContext, _code_id: u64) -> Option<Box<dyn Msg>> {
+        match self {
+            Contracts::Cw20Base { .. } => Some(Box::new(cw20_instantiate())),
         }
     }
 
     // This method gets the preprogrammed set up msgs for the contract.
-    fn set_up_msgs(&self) -> Vec<Box<dyn Msg>> {
+    fn set_up_msgs(&self, ctx: &DeployContext) -> Vec<Box<dyn Msg>> {
         match self {
-            Contracts::Cw20Base => CW20_MINT
-                .iter()
-                .map(|x| Box::new(x.clone()) as Box<dyn Msg>)
+            Contracts::Cw20Base => cw20_mint(ctx)
+                .into_iter()
+                .map(|x| Box::new(x) as Box<dyn Msg>)
                 .collect(),
         }
     }